@@ -3,16 +3,39 @@ use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::time::SystemTime;
 
-const DEFAULT_TTL_SECS: u64 = 6 * 3600; // 6 hours
+use crate::provider::DiscoveredRepo;
+
+/// Default discovery cache TTL; override per workspace with `discovery_ttl`.
+pub const DEFAULT_TTL_SECS: u64 = 15 * 60; // 15 minutes
 
 #[derive(Serialize, Deserialize)]
 struct CacheEntry {
+    provider: String,
     org: String,
-    repos: Vec<String>,
+    repos: Vec<DiscoveredRepo>,
     timestamp: u64, // unix epoch seconds
+    /// Per-page listings with their ETags, for conditional requests once the TTL expires
+    #[serde(default)]
+    pages: Vec<ListingPage>,
+}
+
+/// One page of a GitHub repo listing and the `ETag` it was served with.
+/// A `304 Not Modified` to `If-None-Match: <etag>` means `repos` is still current.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ListingPage {
+    /// Listing URL without the query (`.../orgs/<org>/repos`)
+    pub url: String,
+    pub page: usize,
+    pub etag: String,
+    /// Non-archived repos on the page
+    pub repos: Vec<DiscoveredRepo>,
+    /// Raw length of the page, archived repos included (decides whether another page follows)
+    pub count: usize,
 }
 
-fn cache_dir() -> PathBuf {
+/// tend's cache root: `$XDG_CACHE_HOME/tend`, falling back to `~/.cache/tend`.
+/// Every on-disk cache, journal and lock lives in a subdirectory of it.
+pub fn cache_dir() -> PathBuf {
     std::env::var("XDG_CACHE_HOME")
         .map(PathBuf::from)
         .unwrap_or_else(|_| {
@@ -21,15 +44,20 @@ fn cache_dir() -> PathBuf {
                 .join(".cache")
         })
         .join("tend")
-        .join("discovery")
 }
 
-fn cache_path(org: &str) -> PathBuf {
-    cache_dir().join(format!("{org}.json"))
+fn discovery_dir() -> PathBuf {
+    cache_dir().join("discovery")
 }
 
-pub fn read(org: &str) -> Option<Vec<String>> {
-    let path = cache_path(org);
+fn cache_path(provider: &str, org: &str) -> PathBuf {
+    discovery_dir().join(provider).join(format!("{org}.json"))
+}
+
+/// Cached repo list for `(provider, org)`, if written within `ttl_secs`.
+/// Entries from older tend versions (bare name lists) fail to parse and read as a miss.
+pub fn read(provider: &str, org: &str, ttl_secs: u64) -> Option<Vec<DiscoveredRepo>> {
+    let path = cache_path(provider, org);
     let content = std::fs::read_to_string(&path).ok()?;
     let entry: CacheEntry = serde_json::from_str(&content).ok()?;
 
@@ -37,15 +65,25 @@ pub fn read(org: &str) -> Option<Vec<String>> {
         .duration_since(SystemTime::UNIX_EPOCH)
         .ok()?
         .as_secs();
-    if now.saturating_sub(entry.timestamp) > DEFAULT_TTL_SECS {
+    if now.saturating_sub(entry.timestamp) > ttl_secs {
         return None;
     }
 
     Some(entry.repos)
 }
 
-pub fn write(org: &str, repos: &[String]) -> Result<()> {
-    let dir = cache_dir();
+/// Cached listing pages for `(provider, org)` regardless of age; empty when
+/// there is no entry or it predates ETag support.
+pub fn read_pages(provider: &str, org: &str) -> Vec<ListingPage> {
+    std::fs::read_to_string(cache_path(provider, org))
+        .ok()
+        .and_then(|content| serde_json::from_str::<CacheEntry>(&content).ok())
+        .map(|entry| entry.pages)
+        .unwrap_or_default()
+}
+
+pub fn write(provider: &str, org: &str, repos: &[DiscoveredRepo], pages: &[ListingPage]) -> Result<()> {
+    let dir = discovery_dir().join(provider);
     std::fs::create_dir_all(&dir)?;
 
     let now = SystemTime::now()
@@ -53,12 +91,14 @@ pub fn write(org: &str, repos: &[String]) -> Result<()> {
         .as_secs();
 
     let entry = CacheEntry {
+        provider: provider.to_string(),
         org: org.to_string(),
         repos: repos.to_vec(),
         timestamp: now,
+        pages: pages.to_vec(),
     };
 
     let json = serde_json::to_string_pretty(&entry)?;
-    std::fs::write(cache_path(org), json)?;
+    std::fs::write(cache_path(provider, org), json)?;
     Ok(())
 }
@@ -0,0 +1,93 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Consecutive failed clones after which a repo counts as flaky: it is
+/// reported on every sync and `tend sync --skip-flaky` leaves it out.
+pub const FLAKY_AFTER: u32 = 3;
+
+/// Consecutive clone failures per repo in one workspace. A successful clone
+/// resets the repo's count.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct CloneFailures {
+    #[serde(default)]
+    pub repos: BTreeMap<String, u32>,
+}
+
+impl CloneFailures {
+    /// Consecutive failures recorded for `repo` (0 if none).
+    pub fn count(&self, repo: &str) -> u32 {
+        self.repos.get(repo).copied().unwrap_or(0)
+    }
+
+    pub fn is_flaky(&self, repo: &str) -> bool {
+        self.count(repo) >= FLAKY_AFTER
+    }
+
+    /// Record one clone attempt; returns the repo's new failure count.
+    pub fn record(&mut self, repo: &str, succeeded: bool) -> u32 {
+        if succeeded {
+            self.repos.remove(repo);
+            return 0;
+        }
+        let count = self.repos.entry(repo.to_string()).or_insert(0);
+        *count += 1;
+        *count
+    }
+}
+
+/// Load a workspace's failure counts. A missing or unreadable file reads as
+/// no failures: the counts only drive warnings, never correctness.
+pub fn load(workspace_name: &str) -> CloneFailures {
+    std::fs::read_to_string(failures_path(workspace_name))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Replace the workspace's failure counts, removing the file once none are left.
+pub fn save(workspace_name: &str, failures: &CloneFailures) -> Result<()> {
+    let path = failures_path(workspace_name);
+    if failures.repos.is_empty() {
+        if path.exists() {
+            std::fs::remove_file(&path)
+                .with_context(|| format!("removing clone failures {}", path.display()))?;
+        }
+        return Ok(());
+    }
+
+    let dir = failures_dir();
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("creating clone failures dir {}", dir.display()))?;
+    std::fs::write(&path, serde_json::to_string_pretty(failures)?)
+        .with_context(|| format!("writing clone failures {}", path.display()))?;
+    Ok(())
+}
+
+fn failures_dir() -> PathBuf {
+    crate::cache::cache_dir().join("clone-failures")
+}
+
+pub(crate) fn failures_path(workspace_name: &str) -> PathBuf {
+    failures_dir().join(format!("{workspace_name}.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_counts_consecutive_failures_and_resets() {
+        let mut failures = CloneFailures::default();
+        for expected in 1..=FLAKY_AFTER {
+            assert_eq!(failures.record("gone", false), expected);
+        }
+        assert!(failures.is_flaky("gone"));
+        assert!(!failures.is_flaky("other"));
+
+        assert_eq!(failures.record("gone", true), 0);
+        assert_eq!(failures.count("gone"), 0);
+        assert!(failures.repos.is_empty());
+    }
+}
@@ -1,25 +1,56 @@
 use anyhow::{Context, Result};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::exit::Failure;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Config {
+    /// Repos excluded from every workspace, on top of each workspace's own `exclude`
+    #[serde(default)]
+    pub global_exclude: Vec<String>,
+    /// URL the daemon POSTs workspace errors to (Slack-compatible JSON)
+    #[serde(default)]
+    pub notify_webhook: Option<String>,
+    /// Parallel clones/fetches for workspaces that don't set `jobs`
+    #[serde(default)]
+    pub default_jobs: Option<usize>,
+    /// Workspace fields every workspace inherits unless it sets them itself;
+    /// `{name}` in `base_dir` (and the public/private ones) expands to the workspace name (`~/code/{name}`)
+    #[serde(default, skip_serializing_if = "WorkspaceDefaults::is_empty")]
+    pub defaults: WorkspaceDefaults,
     pub workspaces: Vec<Workspace>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Workspace {
     pub name: String,
     #[serde(default = "default_provider")]
     pub provider: String,
+    /// Executable listing the org's repos for `provider: command` (one name per line on stdout)
+    #[serde(default)]
+    pub provider_command: Option<String>,
     pub base_dir: String,
+    /// Base dir for repos discovery reports as public (instead of `base_dir`)
+    #[serde(default)]
+    pub public_base_dir: Option<String>,
+    /// Base dir for repos discovery reports as private (instead of `base_dir`)
+    #[serde(default)]
+    pub private_base_dir: Option<String>,
     #[serde(default = "default_clone_method")]
     pub clone_method: CloneMethod,
     #[serde(default)]
     pub discover: bool,
     #[serde(default)]
     pub org: Option<String>,
+    /// Whether `org` is a GitHub `org` or `user`; `auto` (default) tries the org endpoint, then the user one
+    #[serde(default)]
+    pub org_kind: OwnerKind,
+    /// GitHub repository search (e.g. `org:acme topic:platform`) resolving the repo list instead of the org listing
+    #[serde(default)]
+    pub search_query: Option<String>,
     #[serde(default)]
     pub exclude: Vec<String>,
     #[serde(default)]
@@ -28,9 +59,179 @@ pub struct Workspace {
     pub flake_deps: HashMap<String, Vec<String>>,
     #[serde(default)]
     pub watch: Option<WatchConfig>,
+    /// Partial clone filter passed as `git clone --filter=<value>` (e.g. `blob:none`, `tree:0`)
+    #[serde(default)]
+    pub clone_filter: Option<String>,
+    /// Plain-text repo list (one name per line, `#` comments); relative to the config file
+    #[serde(default)]
+    pub repos_file: Option<String>,
+    /// Extra environment for git/nix subprocesses run for this workspace (`~`/`$VAR` expanded)
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Local directory name overrides (repo name → dir under base_dir)
+    #[serde(default)]
+    pub dir_map: HashMap<String, String>,
+    /// How repo directories nest under base_dir: `flat` (default), `org`, or `host_org`
+    #[serde(default)]
+    pub layout: Layout,
+    /// Discovery cache TTL in seconds (default 900)
+    #[serde(default)]
+    pub discovery_ttl: Option<u64>,
+    /// Unshallow shallow clones before flake-update commits (otherwise they are refused)
+    #[serde(default)]
+    pub flake_auto_unshallow: bool,
+    /// One flake-update commit per repo (default), or one per updated input
+    #[serde(default)]
+    pub flake_commit_granularity: FlakeCommitGranularity,
+    /// `Name <email>` to author flake-update commits as (default: the repo's git user)
+    #[serde(default)]
+    pub flake_commit_author: Option<String>,
+    /// Arguments appended verbatim to every `nix flake update` (e.g. `--accept-flake-config`)
+    #[serde(default)]
+    pub flake_update_args: Vec<String>,
+    /// Arguments appended verbatim to every `git clone`/`git fetch`; not validated by tend
+    #[serde(default)]
+    pub extra_git_args: Vec<String>,
+    /// GitHub Enterprise host for clone/web URLs (e.g. `github.example.com`; default `github.com`)
+    #[serde(default)]
+    pub host: Option<String>,
+    /// GitHub API base URL; derived as `https://{host}/api/v3` for enterprise hosts when unset
+    #[serde(default)]
+    pub api_base: Option<String>,
+    /// Skip discovered repos whose last push is older than this many days
+    #[serde(default)]
+    pub max_age_days: Option<u64>,
+    /// `working` checkouts (default), or `mirror`/`bare` clones for backups
+    #[serde(default)]
+    pub clone_mode: CloneMode,
+    /// Parallel clones/fetches in this workspace (see `Workspace::jobs`)
+    #[serde(default)]
+    pub jobs: Option<usize>,
+    /// SSH private key for this workspace's git operations (`~` expanded; ssh clone_method only)
+    #[serde(default)]
+    pub ssh_key: Option<String>,
+    /// Name for the remote `git clone` creates (default `origin`)
+    #[serde(default)]
+    pub origin_name: Option<String>,
+    /// Branch to check out after cloning when the remote has it (else the default branch stays)
+    #[serde(default)]
+    pub prefer_branch: Option<String>,
+    /// Clone working checkouts with `--sparse` and check out only these directories.
+    /// Every `sync` also runs `git sparse-checkout list` in each present repo and
+    /// re-applies the set when it differs
+    #[serde(default)]
+    pub sparse_paths: Vec<String>,
+    /// Shell command run once after `tend sync` finishes this workspace (in its base_dir)
+    #[serde(default)]
+    pub post_sync_hook: Option<String>,
+    /// Fetch every configured remote of a working clone, not just the primary one
+    #[serde(default)]
+    pub fetch_all_remotes: bool,
+    /// Labels for selecting groups of workspaces with `--tag` (e.g. `work`, `oss`)
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Copy of `Config::global_exclude`, filled in by `Config::load`
+    #[serde(skip)]
+    pub global_exclude: Vec<String>,
+}
+
+/// Workspace fields set under the top-level `defaults`, each with the same
+/// meaning as on `Workspace`. Every field a workspace leaves unset is taken
+/// from here; `{name}` in the base dirs expands to the workspace name.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct WorkspaceDefaults {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider_command: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_dir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_base_dir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub private_base_dir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clone_method: Option<CloneMethod>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub discover: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub org: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub org_kind: Option<OwnerKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search_query: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exclude: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extra_repos: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flake_deps: Option<HashMap<String, Vec<String>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub watch: Option<WatchConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clone_filter: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repos_file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<HashMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dir_map: Option<HashMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub layout: Option<Layout>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub discovery_ttl: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flake_auto_unshallow: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flake_commit_granularity: Option<FlakeCommitGranularity>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flake_commit_author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flake_update_args: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extra_git_args: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_base: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_age_days: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clone_mode: Option<CloneMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jobs: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssh_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origin_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefer_branch: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sparse_paths: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_sync_hook: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fetch_all_remotes: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+}
+
+impl WorkspaceDefaults {
+    pub fn is_empty(&self) -> bool {
+        self.fields().is_empty()
+    }
+
+    /// The defaults that are set, keyed by workspace field name.
+    fn fields(&self) -> serde_json::Map<String, serde_json::Value> {
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::Object(fields)) => fields,
+            _ => serde_json::Map::new(),
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct WatchConfig {
     /// Enable watch for this workspace
     #[serde(default)]
@@ -68,7 +269,7 @@ pub struct WatchConfig {
 /// When enabled, the daemon runs `nix-audit check --all` after the watch cycle,
 /// optionally auto-fixes violations and propagates fixes across the flake graph.
 /// Results are tracked in a convergence database for trend analysis.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct NixAuditConfig {
     /// Enable nix-audit integration in daemon cycle
     #[serde(default)]
@@ -87,7 +288,7 @@ pub struct NixAuditConfig {
     pub post_hooks: Vec<PostHook>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FileWatch {
     /// Human-readable name for this watch
     pub name: String,
@@ -105,7 +306,7 @@ pub struct FileWatch {
     pub post_hooks: Vec<PostHook>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PostHook {
     /// When to trigger: "after_certify", "after_commit", "after_propagate", "after_all"
     pub trigger: String,
@@ -122,7 +323,7 @@ pub struct PostHook {
     pub continue_on_error: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FlakeInputWatch {
     /// Human-readable name for this watch
     pub name: String,
@@ -150,7 +351,7 @@ pub struct FlakeInputWatch {
     pub post_hooks: Vec<PostHook>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FlakeRefreshConfig {
     /// Enable flake refresh for this workspace
     #[serde(default)]
@@ -165,7 +366,7 @@ pub struct FlakeRefreshConfig {
     /// Required branch — repos not on this branch are skipped (default: "main")
     #[serde(default = "default_branch")]
     pub branch: String,
-    /// Run `git pull origin <branch>` before updating (default: true)
+    /// Pull `<branch>` from the workspace remote before updating (default: true)
     #[serde(default = "default_true")]
     pub pull_before_update: bool,
     /// Shell command to run for updating the flake lock (default: "nix flake update")
@@ -226,7 +427,7 @@ fn default_commit_message() -> String {
     "chore: update flake.lock".to_string()
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum FlakeInputMode {
     Commits,
@@ -237,13 +438,199 @@ fn default_flake_input_mode() -> FlakeInputMode {
     FlakeInputMode::Commits
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum CloneMethod {
     Ssh,
     Https,
 }
 
+/// How repos are cloned: a checked-out working tree, or a bare repo with no
+/// working tree (`mirror` also copies every ref and prunes on fetch).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CloneMode {
+    #[default]
+    Working,
+    Mirror,
+    Bare,
+}
+
+impl CloneMode {
+    /// Mirror and bare clones have no working tree.
+    pub fn is_bare(self) -> bool {
+        self != Self::Working
+    }
+}
+
+/// Which GitHub listing endpoint discovery uses for an owner. `auto` tries
+/// `/orgs` and falls back to `/users` on 404; the others skip the fallback.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default, JsonSchema, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OwnerKind {
+    #[default]
+    Auto,
+    Org,
+    User,
+}
+
+/// How repo directories nest under `base_dir`: `flat` (`<repo>`), `org`
+/// (`<org>/<repo>`), or `host_org` (`<host>/<org>/<repo>`).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Layout {
+    #[default]
+    Flat,
+    Org,
+    HostOrg,
+}
+
+/// How flake-update commits a repo's lock changes. `per_input` runs
+/// `nix flake update <input>` and commits once per input, then pushes once.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FlakeCommitGranularity {
+    #[default]
+    PerRepo,
+    PerInput,
+}
+
+/// On-disk config format, chosen by file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Default, clap::ValueEnum)]
+pub enum ConfigFormat {
+    #[default]
+    Yaml,
+    Toml,
+    Json,
+}
+
+impl ConfigFormat {
+    /// `.toml` → TOML, `.json` → JSON, anything else (`.yaml`, `.yml`) → YAML.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::Toml,
+            Some("json") => Self::Json,
+            _ => Self::Yaml,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Yaml => "yaml",
+            Self::Toml => "toml",
+            Self::Json => "json",
+        }
+    }
+
+    /// Deserialize `contents` in this format.
+    pub fn parse<T: serde::de::DeserializeOwned>(self, contents: &str) -> Result<T> {
+        Ok(match self {
+            Self::Yaml => serde_yaml_ng::from_str(contents)?,
+            Self::Toml => toml::from_str(contents)?,
+            Self::Json => serde_json::from_str(contents)?,
+        })
+    }
+
+    /// Serialize `value` in this format.
+    pub fn to_string<T: Serialize>(self, value: &T) -> Result<String> {
+        Ok(match self {
+            Self::Yaml => serde_yaml_ng::to_string(value)?,
+            Self::Toml => toml::to_string_pretty(value)?,
+            Self::Json => serde_json::to_string_pretty(value)? + "\n",
+        })
+    }
+}
+
+/// A `defaults` value as workspace `name` inherits it: `{name}` in `base_dir`
+/// becomes the workspace name, everything else is copied.
+fn inherited_value(key: &str, value: &serde_json::Value, name: &str) -> serde_json::Value {
+    match (key, value) {
+        ("base_dir" | "public_base_dir" | "private_base_dir", serde_json::Value::String(template)) => template.replace("{name}", name).into(),
+        _ => value.clone(),
+    }
+}
+
+/// Remove null-valued keys from every object in `value`.
+fn drop_nulls(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            map.retain(|_, v| !v.is_null());
+            map.values_mut().for_each(drop_nulls);
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(drop_nulls),
+        _ => {}
+    }
+}
+
+/// Environment variable naming the config file when `--config` isn't given.
+pub const CONFIG_ENV: &str = "TEND_CONFIG";
+
+/// `$TEND_CONFIG`'s path (`~` expanded) when set and non-empty; it must be a file.
+fn config_env_path(value: Option<String>) -> Result<Option<PathBuf>> {
+    let Some(value) = value.filter(|v| !v.trim().is_empty()) else {
+        return Ok(None);
+    };
+    let path = PathBuf::from(shellexpand::tilde(value.trim()).as_ref());
+    if !path.is_file() {
+        let problem = if path.exists() { "is not a file" } else { "does not exist" };
+        return Err(Failure::Config.tag(anyhow::anyhow!(
+            "{CONFIG_ENV} points at {}, which {problem}",
+            path.display()
+        )));
+    }
+    Ok(Some(path))
+}
+
+/// Parallel clones/fetches when neither the CLI, workspace, nor config sets one.
+pub const DEFAULT_JOBS: usize = 4;
+
+/// The values serde fills in for an otherwise empty workspace entry.
+impl Default for Workspace {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            provider: default_provider(),
+            provider_command: None,
+            base_dir: String::new(),
+            public_base_dir: None,
+            private_base_dir: None,
+            clone_method: default_clone_method(),
+            discover: false,
+            org: None,
+            org_kind: OwnerKind::default(),
+            search_query: None,
+            exclude: Vec::new(),
+            extra_repos: Vec::new(),
+            flake_deps: HashMap::new(),
+            watch: None,
+            clone_filter: None,
+            repos_file: None,
+            env: HashMap::new(),
+            dir_map: HashMap::new(),
+            layout: Layout::default(),
+            discovery_ttl: None,
+            flake_auto_unshallow: false,
+            flake_commit_granularity: FlakeCommitGranularity::default(),
+            flake_commit_author: None,
+            flake_update_args: Vec::new(),
+            extra_git_args: Vec::new(),
+            host: None,
+            api_base: None,
+            max_age_days: None,
+            clone_mode: CloneMode::default(),
+            jobs: None,
+            ssh_key: None,
+            origin_name: None,
+            prefer_branch: None,
+            sparse_paths: Vec::new(),
+            post_sync_hook: None,
+            fetch_all_remotes: false,
+            tags: Vec::new(),
+            global_exclude: Vec::new(),
+        }
+    }
+}
+
 fn default_provider() -> String {
     "github".to_string()
 }
@@ -254,11 +641,159 @@ fn default_clone_method() -> CloneMethod {
 
 impl Config {
     pub fn load(path: &Path) -> Result<Self> {
-        let contents =
-            std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
-        let config: Config =
-            serde_yaml_ng::from_str(&contents).with_context(|| format!("parsing {}", path.display()))?;
-        Ok(config)
+        let config = Self::read(path)?;
+        Ok(config.resolved(path.parent().unwrap_or_else(|| Path::new("."))))
+    }
+
+    /// `load` for `--config -`: YAML from stdin, with relative `repos_file`
+    /// paths taken from the current directory. Stdin is read once; later
+    /// loads (daemon cycles) parse the same text again.
+    pub fn load_stdin() -> Result<Self> {
+        static CONTENTS: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+        let contents = match CONTENTS.get() {
+            Some(contents) => contents,
+            None => {
+                let mut contents = String::new();
+                std::io::Read::read_to_string(&mut std::io::stdin(), &mut contents)
+                    .context("reading config from stdin")
+                    .map_err(|e| Failure::Config.tag(e))?;
+                CONTENTS.get_or_init(|| contents)
+            }
+        };
+        Self::parse_stdin(contents)
+    }
+
+    fn parse_stdin(contents: &str) -> Result<Self> {
+        let config = Self::parse(ConfigFormat::Yaml, contents)
+            .context("parsing config from stdin")
+            .map_err(|e| Failure::Config.tag(e))?;
+        Ok(config.resolved(Path::new(".")))
+    }
+
+    /// Fill in what `load` derives rather than reads: global excludes and
+    /// default jobs per workspace, and `repos_file` anchored at `config_dir`.
+    fn resolved(mut self, config_dir: &Path) -> Self {
+        for ws in &mut self.workspaces {
+            ws.global_exclude = self.global_exclude.clone();
+            ws.jobs = ws.jobs.or(self.default_jobs);
+            // Anchor relative repos_file paths to the config file's directory
+            if let Some(ref file) = ws.repos_file {
+                let expanded = PathBuf::from(shellexpand::tilde(file).as_ref());
+                if expanded.is_relative() {
+                    ws.repos_file = Some(config_dir.join(expanded).to_string_lossy().to_string());
+                }
+            }
+        }
+        self
+    }
+
+    /// Drop the global excludes for this run (`--no-default-excludes`).
+    pub fn without_global_excludes(mut self) -> Self {
+        for ws in &mut self.workspaces {
+            ws.global_exclude.clear();
+        }
+        self
+    }
+
+    /// Parse the config file as written, without resolving relative paths.
+    /// Use this (not `load`) when the config will be saved back.
+    /// The format follows the file extension (YAML unless `.toml`/`.json`).
+    pub fn read(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("reading {}", path.display()))
+            .map_err(|e| Failure::Config.tag(e))?;
+        Self::parse(ConfigFormat::from_path(path), &contents)
+            .with_context(|| format!("parsing {}", path.display()))
+            .map_err(|e| Failure::Config.tag(e))
+    }
+
+    /// Deserialize a config, filling each workspace's unset fields from
+    /// `defaults` first. Each merged workspace must still parse on its own.
+    pub fn parse(format: ConfigFormat, contents: &str) -> Result<Self> {
+        let mut raw: serde_json::Value = format.parse(contents)?;
+        let defaults = match raw.get("defaults") {
+            Some(defaults) if !defaults.is_null() => {
+                serde_json::from_value::<WorkspaceDefaults>(defaults.clone()).context("defaults")?.fields()
+            }
+            _ => serde_json::Map::new(),
+        };
+        if defaults.is_empty() {
+            // Parse the text directly so errors keep their line numbers
+            return format.parse(contents);
+        }
+
+        let workspaces = raw
+            .get_mut("workspaces")
+            .and_then(|w| w.as_array_mut())
+            .map(Vec::as_mut_slice)
+            .unwrap_or_default();
+        for (i, ws) in workspaces.iter_mut().enumerate() {
+            let Some(fields) = ws.as_object_mut() else {
+                continue;
+            };
+            let name = fields.get("name").and_then(|n| n.as_str()).unwrap_or_default().to_string();
+            for (key, value) in &defaults {
+                if !fields.contains_key(key) {
+                    fields.insert(key.clone(), inherited_value(key, value, &name));
+                }
+            }
+            serde_json::from_value::<Workspace>(ws.clone()).with_context(|| {
+                let label = if name.is_empty() { format!("#{}", i + 1) } else { name.clone() };
+                format!("workspace {label} (with defaults applied)")
+            })?;
+        }
+        Ok(serde_json::from_value(raw)?)
+    }
+
+    /// Serialize the config back to `path` in the format matching its extension.
+    /// Workspace fields equal to what `defaults` would give them are left out.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let format = ConfigFormat::from_path(path);
+        let defaults = self.defaults.fields();
+        let contents = if defaults.is_empty() {
+            format.to_string(self)
+        } else {
+            let mut raw = serde_json::to_value(self).context("serializing config")?;
+            let workspaces = raw
+                .get_mut("workspaces")
+                .and_then(|w| w.as_array_mut())
+                .map(Vec::as_mut_slice)
+                .unwrap_or_default();
+            for fields in workspaces.iter_mut().filter_map(|ws| ws.as_object_mut()) {
+                let name = fields.get("name").and_then(|n| n.as_str()).unwrap_or_default().to_string();
+                for (key, value) in &defaults {
+                    if fields.get(key) == Some(&inherited_value(key, value, &name)) {
+                        fields.remove(key);
+                    }
+                }
+            }
+            // Unset options serialize as null here, which TOML can't represent
+            drop_nulls(&mut raw);
+            format.to_string(&raw)
+        }
+        .context("serializing config")?;
+        std::fs::write(path, contents).with_context(|| format!("writing {}", path.display()))
+    }
+
+    /// Look up a workspace by name for editing.
+    pub fn workspace_mut(&mut self, name: &str) -> Result<&mut Workspace> {
+        self.workspaces
+            .iter_mut()
+            .find(|ws| ws.name == name)
+            .with_context(|| format!("no workspace named {name} in config"))
+    }
+
+    /// JSON Schema for the config file, for YAML language server validation/completion.
+    pub fn json_schema() -> Result<String> {
+        let schema = schemars::schema_for!(Config);
+        serde_json::to_string_pretty(&schema).context("serializing config schema")
+    }
+
+    /// The config file named by `$TEND_CONFIG`, if set. Unlike the other
+    /// defaults, a file that doesn't exist is an error: falling through to
+    /// another config would run against the wrong workspaces.
+    pub fn env_path() -> Result<Option<PathBuf>> {
+        config_env_path(std::env::var(CONFIG_ENV).ok())
     }
 
     /// Discover the default config file path using shikumi.
@@ -272,7 +807,7 @@ impl Config {
 
         // Try shikumi discovery first (TEND_CONFIG env, then tend/tend.yaml, etc.)
         if let Ok(path) = ConfigDiscovery::new("tend")
-            .env_override("TEND_CONFIG")
+            .env_override(CONFIG_ENV)
             .formats(&[Format::Yaml])
             .discover()
         {
@@ -292,37 +827,680 @@ impl Config {
 }
 
 impl Workspace {
+    /// How many clones/fetches run at once. Precedence, highest first:
+    /// `--jobs` (applied to `jobs` by the CLI), the workspace's `jobs`, the
+    /// config's `default_jobs` (applied by `Config::load`), then `DEFAULT_JOBS`.
+    pub fn jobs(&self) -> usize {
+        self.jobs.unwrap_or(DEFAULT_JOBS).max(1)
+    }
+
+    /// Remote name repos are cloned with: `origin_name`, or `origin`.
+    pub fn remote_name(&self) -> &str {
+        self.origin_name.as_deref().unwrap_or("origin")
+    }
+
+    /// Whether the workspace carries any of `tags` (or all of them with `all`).
+    /// An empty `tags` matches every workspace.
+    pub fn matches_tags(&self, tags: &[String], all: bool) -> bool {
+        if all {
+            tags.iter().all(|t| self.tags.contains(t))
+        } else {
+            tags.is_empty() || tags.iter().any(|t| self.tags.contains(t))
+        }
+    }
+
     /// Resolve base_dir with shell expansion (~ → home dir)
     pub fn resolved_base_dir(&self) -> Result<PathBuf> {
         let expanded = shellexpand::tilde(&self.base_dir);
         Ok(PathBuf::from(expanded.as_ref()))
     }
 
+    /// Base dir for a repo of the given visibility: `private_base_dir` or
+    /// `public_base_dir` when set, else `base_dir` (also for unknown visibility).
+    pub fn base_dir_for(&self, private: Option<bool>) -> Result<PathBuf> {
+        let routed = match private {
+            Some(true) => self.private_base_dir.as_deref(),
+            Some(false) => self.public_base_dir.as_deref(),
+            None => None,
+        };
+        match routed {
+            Some(dir) => Ok(PathBuf::from(shellexpand::tilde(dir).as_ref())),
+            None => self.resolved_base_dir(),
+        }
+    }
+
+    /// Every base dir this workspace's repos can live in, `base_dir` first.
+    pub fn resolved_base_dirs(&self) -> Result<Vec<PathBuf>> {
+        let mut dirs = vec![self.resolved_base_dir()?];
+        for private in [false, true] {
+            let dir = self.base_dir_for(Some(private))?;
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+        Ok(dirs)
+    }
+
+    /// Workspace `env` entries with `~` and `$VAR` expanded in values, plus a
+    /// `GIT_SSH_COMMAND` for `ssh_key` (an explicit `env` entry wins).
+    /// Applied per subprocess via `Command::envs`, never to tend's own environment,
+    /// so overrides don't leak between workspaces in one daemon run.
+    pub fn resolved_env(&self) -> Result<HashMap<String, String>> {
+        let mut env: HashMap<String, String> = self
+            .env
+            .iter()
+            .map(|(key, value)| {
+                let expanded = shellexpand::full(value)
+                    .with_context(|| format!("expanding env {key} for workspace {}", self.name))?;
+                Ok((key.clone(), expanded.into_owned()))
+            })
+            .collect::<Result<_>>()?;
+
+        if let (CloneMethod::Ssh, Some(key)) = (&self.clone_method, &self.ssh_key) {
+            let key = shellexpand::tilde(key);
+            // git runs GIT_SSH_COMMAND through the shell; single-quote the path
+            let quoted = format!("'{}'", key.replace('\'', r"'\''"));
+            env.entry("GIT_SSH_COMMAND".to_string())
+                .or_insert_with(|| format!("ssh -i {quoted} -o IdentitiesOnly=yes"));
+        }
+        Ok(env)
+    }
+
+    /// Append a repo to `extra_repos`. Returns false if it was already listed.
+    pub fn add_extra_repo(&mut self, repo: &str) -> bool {
+        push_unique(&mut self.extra_repos, repo)
+    }
+
+    /// Append a repo to `exclude`. Returns false if it was already listed.
+    pub fn add_exclude(&mut self, repo: &str) -> bool {
+        push_unique(&mut self.exclude, repo)
+    }
+
+    /// Local directory name for a repo (`dir_map` entry, falling back to the repo name)
+    pub fn repo_dir<'a>(&'a self, repo: &'a str) -> &'a str {
+        self.dir_map.get(repo).map(String::as_str).unwrap_or(repo)
+    }
+
+    /// Directory this workspace's repos live in: `base_dir`, nested by `layout`.
+    pub fn repos_root(&self, base_dir: &Path) -> PathBuf {
+        let owner = self.owner().trim_start_matches('~');
+        match self.layout {
+            Layout::Flat => base_dir.to_path_buf(),
+            Layout::Org => base_dir.join(owner),
+            Layout::HostOrg => base_dir.join(self.git_host()).join(owner),
+        }
+    }
+
+    /// On-disk path of a repo's clone. Every module locating a repo goes through
+    /// here so sync, status, and flake-update agree on the layout.
+    pub fn repo_path(&self, base_dir: &Path, repo: &str) -> PathBuf {
+        self.repos_root(base_dir).join(self.repo_dir(repo))
+    }
+
+    /// Host the repos are cloned from (`git.sr.ht` for Sourcehut)
+    fn git_host(&self) -> &str {
+        if self.provider == "sourcehut" {
+            "git.sr.ht"
+        } else {
+            self.github_host()
+        }
+    }
+
+    /// Git host for clone and web URLs (`host`, falling back to `github.com`)
+    pub fn github_host(&self) -> &str {
+        self.host.as_deref().unwrap_or("github.com")
+    }
+
+    /// GitHub API base for discovery: `api_base`, else `https://{host}/api/v3` for an
+    /// enterprise host, else `None` (public api.github.com).
+    pub fn github_api_base(&self) -> Option<String> {
+        if let Some(ref base) = self.api_base {
+            return Some(base.trim_end_matches('/').to_string());
+        }
+        match self.host.as_deref() {
+            Some(host) if host != "github.com" => Some(format!("https://{host}/api/v3")),
+            _ => None,
+        }
+    }
+
+    /// GitHub owner for this workspace (`org`, falling back to the workspace name)
+    pub fn owner(&self) -> &str {
+        self.org.as_deref().unwrap_or(&self.name)
+    }
+
     /// Build the clone URL for a repo name
     pub fn clone_url(&self, repo_name: &str) -> String {
-        let org = self.org.as_deref().unwrap_or(&self.name);
+        let org = self.owner();
+        if self.provider == "sourcehut" {
+            let user = org.trim_start_matches('~');
+            return match self.clone_method {
+                CloneMethod::Ssh => format!("git@git.sr.ht:~{user}/{repo_name}"),
+                CloneMethod::Https => format!("https://git.sr.ht/~{user}/{repo_name}"),
+            };
+        }
+        let host = self.github_host();
         match self.clone_method {
-            CloneMethod::Ssh => format!("git@github.com:{org}/{repo_name}.git"),
-            CloneMethod::Https => format!("https://github.com/{org}/{repo_name}.git"),
+            CloneMethod::Ssh => format!("git@{host}:{org}/{repo_name}.git"),
+            CloneMethod::Https => format!("https://{host}/{org}/{repo_name}.git"),
+        }
+    }
+
+    /// Build the browsable web URL for a repo name
+    pub fn web_url(&self, repo_name: &str) -> String {
+        if self.provider == "sourcehut" {
+            let user = self.owner().trim_start_matches('~');
+            return format!("https://git.sr.ht/~{user}/{repo_name}");
+        }
+        format!("https://{}/{}/{repo_name}", self.github_host(), self.owner())
+    }
+}
+
+fn push_unique(list: &mut Vec<String>, item: &str) -> bool {
+    if list.iter().any(|existing| existing == item) {
+        return false;
+    }
+    list.push(item.to_string());
+    true
+}
+
+/// Rename a repo everywhere it is referenced in a raw config document:
+/// `extra_repos`, `exclude`, `flake_deps` keys and values, `dir_map` keys, and
+/// watch `flake_input_watches[].repo` / `flake_refresh` include/exclude.
+///
+/// Works on the YAML tree rather than `Config` so keys the user never set aren't
+/// written back. Returns a `workspace: field` description of each edit.
+pub fn rename_repo(doc: &mut serde_yaml_ng::Value, old: &str, new: &str) -> Vec<String> {
+    use serde_yaml_ng::Value;
+
+    fn rename_in_seq(value: Option<&mut Value>, old: &str, new: &str) -> bool {
+        let mut changed = false;
+        if let Some(Value::Sequence(items)) = value {
+            for item in items {
+                if item.as_str() == Some(old) {
+                    *item = Value::String(new.to_string());
+                    changed = true;
+                }
+            }
         }
+        changed
     }
+
+    // Rebuild the mapping so the renamed key keeps its position
+    fn rename_key(value: Option<&mut Value>, old: &str, new: &str) -> bool {
+        let Some(Value::Mapping(map)) = value else {
+            return false;
+        };
+        if !map.contains_key(old) {
+            return false;
+        }
+        let entries = std::mem::take(map);
+        for (k, v) in entries {
+            let k = if k.as_str() == Some(old) { Value::String(new.to_string()) } else { k };
+            map.insert(k, v);
+        }
+        true
+    }
+
+    let mut edits = Vec::new();
+    let Some(workspaces) = doc.get_mut("workspaces").and_then(Value::as_sequence_mut) else {
+        return edits;
+    };
+
+    for ws in workspaces {
+        let name = ws.get("name").and_then(Value::as_str).unwrap_or("?").to_string();
+        let mut record = |changed: bool, field: &str| {
+            if changed {
+                edits.push(format!("{name}: {field}"));
+            }
+        };
+
+        record(rename_in_seq(ws.get_mut("extra_repos"), old, new), "extra_repos");
+        record(rename_in_seq(ws.get_mut("exclude"), old, new), "exclude");
+        record(rename_key(ws.get_mut("flake_deps"), old, new), "flake_deps key");
+        let mut dep_values = false;
+        if let Some(Value::Mapping(deps)) = ws.get_mut("flake_deps") {
+            for (_, inputs) in deps.iter_mut() {
+                dep_values |= rename_in_seq(Some(inputs), old, new);
+            }
+        }
+        record(dep_values, "flake_deps inputs");
+        record(rename_key(ws.get_mut("dir_map"), old, new), "dir_map key");
+
+        if let Some(watch) = ws.get_mut("watch") {
+            let mut watch_repo = false;
+            if let Some(Value::Sequence(fiws)) = watch.get_mut("flake_input_watches") {
+                for fiw in fiws {
+                    if fiw.get("repo").and_then(Value::as_str) == Some(old) {
+                        fiw["repo"] = Value::String(new.to_string());
+                        watch_repo = true;
+                    }
+                }
+            }
+            record(watch_repo, "watch.flake_input_watches repo");
+            if let Some(refresh) = watch.get_mut("flake_refresh") {
+                record(rename_in_seq(refresh.get_mut("include"), old, new), "watch.flake_refresh.include");
+                record(rename_in_seq(refresh.get_mut("exclude"), old, new), "watch.flake_refresh.exclude");
+            }
+        }
+    }
+
+    edits
 }
 
-/// Generate a starter config file
-pub fn generate_starter_config() -> String {
+/// Generate a starter config file in the given format
+pub fn generate_starter_config(format: ConfigFormat) -> String {
     let config = Config {
+        global_exclude: vec![],
+        notify_webhook: None,
+        default_jobs: None,
+        defaults: Default::default(),
         workspaces: vec![Workspace {
             name: "my-org".to_string(),
-            provider: "github".to_string(),
             base_dir: "~/code/github/my-org".to_string(),
-            clone_method: CloneMethod::Ssh,
             discover: true,
             org: Some("my-org".to_string()),
             exclude: vec![".github".to_string()],
-            extra_repos: vec![],
-            flake_deps: HashMap::new(),
-            watch: None,
+            ..Default::default()
+        }],
+    };
+    format.to_string(&config).unwrap()
+}
+
+/// Generate a config pinning discovered repos as `extra_repos` (with `discover: false`).
+pub fn generate_discovered_config(provider: &str, org: &str, repos: &[String]) -> String {
+    let config = Config {
+        global_exclude: vec![],
+        notify_webhook: None,
+        default_jobs: None,
+        defaults: Default::default(),
+        workspaces: vec![Workspace {
+            name: org.trim_start_matches('~').to_string(),
+            provider: provider.to_string(),
+            base_dir: format!("~/code/{provider}/{}", org.trim_start_matches('~')),
+            org: Some(org.to_string()),
+            extra_repos: repos.to_vec(),
+            ..Default::default()
         }],
     };
     serde_yaml_ng::to_string(&config).unwrap()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_workspace(org: Option<&str>, clone_method: CloneMethod) -> Workspace {
+        Workspace {
+            name: "my-ws".to_string(),
+            base_dir: "~/code".to_string(),
+            clone_method,
+            org: org.map(|s| s.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_workspace_default_matches_serde_defaults() {
+        let parsed: Workspace = serde_yaml_ng::from_str("name: ws\nbase_dir: ~/code").unwrap();
+        let built = Workspace {
+            name: "ws".to_string(),
+            base_dir: "~/code".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), serde_json::to_value(&built).unwrap());
+    }
+
+    #[test]
+    fn test_enterprise_host_urls_and_api_base() {
+        let mut ws = make_workspace(Some("acme"), CloneMethod::Ssh);
+        assert_eq!(ws.github_api_base(), None);
+
+        ws.host = Some("github.example.com".to_string());
+        assert_eq!(ws.clone_url("tools"), "git@github.example.com:acme/tools.git");
+        assert_eq!(ws.web_url("tools"), "https://github.example.com/acme/tools");
+        assert_eq!(ws.github_api_base().as_deref(), Some("https://github.example.com/api/v3"));
+
+        ws.api_base = Some("https://ghe-api.example.com/api/v3/".to_string());
+        assert_eq!(ws.github_api_base().as_deref(), Some("https://ghe-api.example.com/api/v3"));
+    }
+
+    #[test]
+    fn test_starter_config_round_trips_in_every_format() {
+        for format in [ConfigFormat::Yaml, ConfigFormat::Toml, ConfigFormat::Json] {
+            let text = generate_starter_config(format);
+            let config: Config = format.parse(&text).unwrap();
+            assert_eq!(config.workspaces[0].name, "my-org", "{format:?}");
+            assert_eq!(config.workspaces[0].exclude, vec![".github"], "{format:?}");
+        }
+    }
+
+    #[test]
+    fn test_serialized_config_shows_defaults() {
+        let config: Config = ConfigFormat::Yaml
+            .parse("workspaces:\n  - name: a\n    base_dir: /tmp/a\n")
+            .unwrap();
+        let yaml = ConfigFormat::Yaml.to_string(&config).unwrap();
+        assert!(yaml.contains("clone_method: ssh"));
+        assert!(yaml.contains("provider: github"));
+        for format in [ConfigFormat::Toml, ConfigFormat::Json] {
+            let text = format.to_string(&config).unwrap();
+            let back: Config = format.parse(&text).unwrap();
+            assert_eq!(back.workspaces[0].clone_method, CloneMethod::Ssh, "{format:?}");
+        }
+    }
+
+    #[test]
+    fn test_load_copies_global_exclude_into_workspaces() {
+        let dir = std::env::temp_dir().join(format!("tend-test-global-exclude-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.yaml");
+        std::fs::write(
+            &path,
+            "global_exclude: [.github, renovate-bot]\nworkspaces:\n  - name: a\n    base_dir: /tmp/a\n    exclude: [docs]\n",
+        )
+        .unwrap();
+
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.workspaces[0].exclude, vec!["docs"]);
+        assert_eq!(config.workspaces[0].global_exclude, vec![".github", "renovate-bot"]);
+        assert!(config.without_global_excludes().workspaces[0].global_exclude.is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_defaults_fill_unset_workspace_fields() {
+        let text = "defaults:\n  base_dir: ~/code/{name}\n  clone_method: https\n  discover: true\n\
+                    workspaces:\n  - name: a\n  - name: b\n    base_dir: /srv/b\n    clone_method: ssh\n";
+        let config = Config::parse(ConfigFormat::Yaml, text).unwrap();
+        let (a, b) = (&config.workspaces[0], &config.workspaces[1]);
+        assert_eq!((a.base_dir.as_str(), &a.clone_method, a.discover), ("~/code/a", &CloneMethod::Https, true));
+        assert_eq!((b.base_dir.as_str(), &b.clone_method, b.discover), ("/srv/b", &CloneMethod::Ssh, true));
+
+        // Saving leaves inherited values to `defaults` again
+        let dir = std::env::temp_dir().join(format!("tend-test-defaults-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for format in [ConfigFormat::Yaml, ConfigFormat::Toml, ConfigFormat::Json] {
+            let path = dir.join(format!("config.{}", format.extension()));
+            config.save(&path).unwrap();
+            let saved: serde_json::Value = format.parse(&std::fs::read_to_string(&path).unwrap()).unwrap();
+            assert!(saved["workspaces"][0].get("base_dir").is_none(), "{format:?}");
+            assert_eq!(saved["workspaces"][1]["base_dir"], "/srv/b", "{format:?}");
+            assert_eq!(Config::read(&path).unwrap().workspaces[0].base_dir, "~/code/a", "{format:?}");
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let err = Config::parse(ConfigFormat::Yaml, "defaults:\n  discover: true\nworkspaces:\n  - name: c\n")
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("workspace c (with defaults applied)"), "{err:#}");
+        assert!(format!("{err:#}").contains("base_dir"), "{err:#}");
+
+        // Typos and non-workspace fields in `defaults` are rejected
+        for text in ["defaults:\n  discovr: true\nworkspaces: []\n", "defaults:\n  name: x\nworkspaces: []\n"] {
+            let err = Config::parse(ConfigFormat::Yaml, text).unwrap_err();
+            assert!(format!("{err:#}").contains("unknown field"), "{err:#}");
+        }
+    }
+
+    #[test]
+    fn test_jobs_precedence() {
+        let dir = std::env::temp_dir().join(format!("tend-test-jobs-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.yaml");
+        std::fs::write(
+            &path,
+            "default_jobs: 2\nworkspaces:\n  - name: a\n    base_dir: /tmp/a\n    jobs: 8\n  - name: b\n    base_dir: /tmp/b\n",
+        )
+        .unwrap();
+
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.workspaces[0].jobs(), 8);
+        assert_eq!(config.workspaces[1].jobs(), 2);
+        let mut ws = make_workspace(None, CloneMethod::Ssh);
+        assert_eq!(ws.jobs(), DEFAULT_JOBS);
+        ws.jobs = Some(0);
+        assert_eq!(ws.jobs(), 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_config_format_from_path() {
+        assert_eq!(ConfigFormat::from_path(Path::new("tend.toml")), ConfigFormat::Toml);
+        assert_eq!(ConfigFormat::from_path(Path::new("tend.json")), ConfigFormat::Json);
+        assert_eq!(ConfigFormat::from_path(Path::new("config.yml")), ConfigFormat::Yaml);
+        assert_eq!(ConfigFormat::from_path(Path::new("config")), ConfigFormat::Yaml);
+    }
+
+    #[test]
+    fn test_add_extra_repo_and_exclude_dedup() {
+        let mut config = Config {
+            global_exclude: vec![],
+            notify_webhook: None,
+            default_jobs: None,
+            defaults: Default::default(),
+            workspaces: vec![make_workspace(None, CloneMethod::Ssh)],
+        };
+        let ws = config.workspace_mut("my-ws").unwrap();
+        assert!(ws.add_extra_repo("tools"));
+        assert!(!ws.add_extra_repo("tools"));
+        assert!(ws.add_exclude("legacy"));
+        assert!(!ws.add_exclude("legacy"));
+        assert_eq!(ws.extra_repos, vec!["tools"]);
+        assert_eq!(ws.exclude, vec!["legacy"]);
+        assert!(config.workspace_mut("nope").is_err());
+    }
+
+    #[test]
+    fn test_rename_repo_rewrites_all_references() {
+        let yaml = r#"
+workspaces:
+  - name: ws
+    base_dir: ~/code
+    extra_repos: [old-lib, other]
+    exclude: [old-lib]
+    flake_deps:
+      app: [old-lib]
+      old-lib: [base]
+      z-last: []
+    dir_map:
+      old-lib: local-lib
+  - name: untouched
+    base_dir: ~/code2
+"#;
+        let mut doc: serde_yaml_ng::Value = serde_yaml_ng::from_str(yaml).unwrap();
+        let edits = rename_repo(&mut doc, "old-lib", "new-lib");
+        assert_eq!(
+            edits,
+            vec![
+                "ws: extra_repos",
+                "ws: exclude",
+                "ws: flake_deps key",
+                "ws: flake_deps inputs",
+                "ws: dir_map key",
+            ]
+        );
+
+        let config: Config = serde_yaml_ng::from_value(doc.clone()).unwrap();
+        let ws = &config.workspaces[0];
+        assert_eq!(ws.extra_repos, vec!["new-lib", "other"]);
+        assert_eq!(ws.exclude, vec!["new-lib"]);
+        assert_eq!(ws.flake_deps["app"], vec!["new-lib"]);
+        assert_eq!(ws.flake_deps["new-lib"], vec!["base"]);
+        assert_eq!(ws.dir_map["new-lib"], "local-lib");
+
+        // Renamed key keeps its position
+        let keys: Vec<&str> = doc["workspaces"][0]["flake_deps"]
+            .as_mapping()
+            .unwrap()
+            .keys()
+            .filter_map(|k| k.as_str())
+            .collect();
+        assert_eq!(keys, vec!["app", "new-lib", "z-last"]);
+        assert!(doc["workspaces"][1].get("extra_repos").is_none());
+    }
+
+    #[test]
+    fn test_sourcehut_urls() {
+        let mut ws = make_workspace(Some("~alice"), CloneMethod::Ssh);
+        ws.provider = "sourcehut".to_string();
+        assert_eq!(ws.clone_url("tools"), "git@git.sr.ht:~alice/tools");
+        assert_eq!(ws.web_url("tools"), "https://git.sr.ht/~alice/tools");
+
+        ws.org = Some("alice".to_string());
+        ws.clone_method = CloneMethod::Https;
+        assert_eq!(ws.clone_url("tools"), "https://git.sr.ht/~alice/tools");
+    }
+
+    #[test]
+    fn test_repo_dir_uses_dir_map() {
+        let mut ws = make_workspace(None, CloneMethod::Ssh);
+        ws.dir_map.insert("utils".to_string(), "org-utils".to_string());
+        assert_eq!(ws.repo_dir("utils"), "org-utils");
+        assert_eq!(ws.repo_dir("other"), "other");
+        assert_eq!(ws.clone_url("utils"), "git@github.com:my-ws/utils.git");
+    }
+
+    #[test]
+    fn test_repo_path_follows_layout() {
+        let mut ws = make_workspace(Some("acme"), CloneMethod::Ssh);
+        ws.dir_map.insert("utils".to_string(), "acme-utils".to_string());
+        let base = Path::new("/code");
+        assert_eq!(ws.repo_path(base, "utils"), Path::new("/code/acme-utils"));
+
+        ws.layout = Layout::Org;
+        assert_eq!(ws.repo_path(base, "utils"), Path::new("/code/acme/acme-utils"));
+
+        ws.layout = Layout::HostOrg;
+        assert_eq!(ws.repo_path(base, "tools"), Path::new("/code/github.com/acme/tools"));
+        ws.host = Some("github.example.com".to_string());
+        assert_eq!(ws.repos_root(base), Path::new("/code/github.example.com/acme"));
+
+        ws.provider = "sourcehut".to_string();
+        ws.org = Some("~alice".to_string());
+        assert_eq!(ws.repos_root(base), Path::new("/code/git.sr.ht/alice"));
+    }
+
+    #[test]
+    fn test_generate_discovered_config_round_trips() {
+        let repos = vec!["alpha".to_string(), "beta".to_string()];
+        let yaml = generate_discovered_config("github", "my-org", &repos);
+        let config: Config = serde_yaml_ng::from_str(&yaml).unwrap();
+        let ws = &config.workspaces[0];
+        assert_eq!(ws.name, "my-org");
+        assert_eq!(ws.org.as_deref(), Some("my-org"));
+        assert!(!ws.discover);
+        assert_eq!(ws.extra_repos, repos);
+    }
+
+    #[test]
+    fn test_resolved_env_expands_values() {
+        let mut ws = make_workspace(None, CloneMethod::Ssh);
+        ws.env.insert("GIT_SSH_KEY".to_string(), "~/.ssh/org_key".to_string());
+        let env = ws.resolved_env().unwrap();
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(env["GIT_SSH_KEY"], format!("{}/.ssh/org_key", home.display()));
+
+        ws.env.insert("BROKEN".to_string(), "$TEND_TEST_UNSET_VAR".to_string());
+        assert!(ws.resolved_env().is_err());
+    }
+
+    #[test]
+    fn test_ssh_key_sets_git_ssh_command_for_ssh_only() {
+        let mut ws = make_workspace(None, CloneMethod::Ssh);
+        ws.ssh_key = Some("~/.ssh/org_key".to_string());
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(
+            ws.resolved_env().unwrap()["GIT_SSH_COMMAND"],
+            format!("ssh -i '{}/.ssh/org_key' -o IdentitiesOnly=yes", home.display())
+        );
+
+        ws.env.insert("GIT_SSH_COMMAND".to_string(), "ssh -v".to_string());
+        assert_eq!(ws.resolved_env().unwrap()["GIT_SSH_COMMAND"], "ssh -v");
+
+        ws.env.clear();
+        ws.clone_method = CloneMethod::Https;
+        assert!(!ws.resolved_env().unwrap().contains_key("GIT_SSH_COMMAND"));
+    }
+
+    #[test]
+    fn test_json_schema_reflects_enums_and_defaults() {
+        let schema: serde_json::Value = serde_json::from_str(&Config::json_schema().unwrap()).unwrap();
+        let defs = &schema["definitions"];
+        assert_eq!(defs["CloneMethod"]["enum"], serde_json::json!(["ssh", "https"]));
+        assert_eq!(
+            defs["FlakeCommitGranularity"]["enum"],
+            serde_json::json!(["per_repo", "per_input"])
+        );
+        assert_eq!(defs["Workspace"]["properties"]["clone_method"]["default"], "ssh");
+        assert_eq!(defs["Workspace"]["properties"]["provider"]["default"], "github");
+        let required = defs["Workspace"]["required"].as_array().unwrap();
+        assert!(required.contains(&serde_json::json!("name")));
+        assert!(!required.contains(&serde_json::json!("org")));
+        assert_eq!(defs["WorkspaceDefaults"]["additionalProperties"], false);
+        assert!(defs["WorkspaceDefaults"]["properties"]["base_dir"].is_object());
+    }
+
+    #[test]
+    fn test_clone_url_and_web_url() {
+        let ws = make_workspace(Some("acme"), CloneMethod::Ssh);
+        assert_eq!(ws.clone_url("tool"), "git@github.com:acme/tool.git");
+        assert_eq!(ws.web_url("tool"), "https://github.com/acme/tool");
+
+        let ws = make_workspace(None, CloneMethod::Https);
+        assert_eq!(ws.clone_url("tool"), "https://github.com/my-ws/tool.git");
+        assert_eq!(ws.web_url("tool"), "https://github.com/my-ws/tool");
+    }
+
+    #[test]
+    fn test_load_resolves_relative_repos_file() {
+        let dir = std::env::temp_dir().join(format!("tend-test-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.yaml");
+        std::fs::write(
+            &path,
+            "workspaces:\n  - name: a\n    base_dir: /tmp/a\n    repos_file: repos.txt\n  - name: b\n    base_dir: /tmp/b\n    repos_file: /abs/repos.txt\n",
+        )
+        .unwrap();
+
+        let config = Config::load(&path).unwrap();
+        assert_eq!(
+            config.workspaces[0].repos_file.as_deref(),
+            Some(dir.join("repos.txt").to_string_lossy().as_ref())
+        );
+        assert_eq!(config.workspaces[1].repos_file.as_deref(), Some("/abs/repos.txt"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+    #[test]
+    fn test_config_env_path_must_exist() {
+        assert!(config_env_path(None).unwrap().is_none());
+        assert!(config_env_path(Some(" ".to_string())).unwrap().is_none());
+
+        let existing = std::env::temp_dir().join(format!("tend-test-config-env-{}.yaml", std::process::id()));
+        std::fs::write(&existing, "workspaces: []\n").unwrap();
+        assert_eq!(
+            config_env_path(Some(existing.to_string_lossy().to_string())).unwrap(),
+            Some(existing.clone())
+        );
+        let _ = std::fs::remove_file(&existing);
+
+        let err = config_env_path(Some("/nonexistent/tend.yaml".to_string())).unwrap_err();
+        assert_eq!(err.to_string(), "TEND_CONFIG points at /nonexistent/tend.yaml, which does not exist");
+        assert_eq!(crate::exit::exit_code(&err), 2);
+
+        let dir = std::env::temp_dir();
+        let err = config_env_path(Some(dir.to_string_lossy().to_string())).unwrap_err();
+        assert!(err.to_string().ends_with("which is not a file"), "{err}");
+    }
+
+    #[test]
+    fn test_parse_stdin_names_stdin_and_anchors_at_cwd() {
+        let config = Config::parse_stdin("workspaces:\n  - name: a\n    base_dir: /tmp/a\n    repos_file: repos.txt\n").unwrap();
+        assert_eq!(config.workspaces[0].repos_file.as_deref(), Some("./repos.txt"));
+
+        let err = Config::parse_stdin("workspaces: [").unwrap_err();
+        assert!(format!("{err:#}").starts_with("parsing config from stdin: "), "{err:#}");
+        assert_eq!(crate::exit::exit_code(&err), 2);
+    }
+}
@@ -1,41 +1,198 @@
 use anyhow::Result;
+use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::path::PathBuf;
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{Notify, Semaphore};
 
-use crate::{display, git, github, load_config, filter_workspaces, sync, watch, watch_cache};
+use crate::{display, git, github, health, load_config, lock, provider, sync, watch, watch_cache, WorkspaceFilter};
 
 /// Options for the daemon command.
 pub struct DaemonOpts {
     pub config: Option<PathBuf>,
-    pub workspace: Option<String>,
+    /// Which workspaces each cycle runs
+    pub filter: WorkspaceFilter,
     pub interval: u64,
     pub fetch: bool,
     pub quiet: bool,
+    /// Maximum concurrent GitHub API requests shared by all workspace tasks
+    pub api_jobs: usize,
+    /// Webhook for workspace errors; falls back to the config's `notify_webhook`
+    pub notify_webhook: Option<String>,
+    /// Serve `/healthz` and `/status` on this address
+    pub health_addr: Option<SocketAddr>,
+    /// Run `git maintenance` in every clean repo each time this many cycles have passed
+    pub maintenance_every: Option<u64>,
+}
+
+/// Minimum gap between two notifications for the same workspace and error.
+const NOTIFY_COOLDOWN: Duration = Duration::from_secs(60 * 60);
+
+/// POSTs daemon errors to a webhook, suppressing repeats of an identical error.
+struct ErrorNotifier {
+    url: String,
+    client: reqwest::Client,
+    /// (workspace, message) → when it was last sent
+    sent: Mutex<HashMap<(String, String), Instant>>,
+}
+
+impl ErrorNotifier {
+    fn new(url: &str) -> Result<Self> {
+        Ok(Self {
+            url: url.to_string(),
+            client: provider::api_client()?,
+            sent: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Record a send of `message` for `workspace` unless one went out within the cooldown.
+    fn should_send(&self, workspace: &str, message: &str, now: Instant) -> bool {
+        let mut sent = self.sent.lock().unwrap_or_else(|e| e.into_inner());
+        let key = (workspace.to_string(), message.to_string());
+        match sent.get(&key) {
+            Some(last) if now.duration_since(*last) < NOTIFY_COOLDOWN => false,
+            _ => {
+                sent.insert(key, now);
+                true
+            }
+        }
+    }
+
+    /// Best-effort POST; a failed delivery is logged, never propagated.
+    async fn notify(&self, workspace: &str, err: &anyhow::Error, cycle: u64) {
+        let message = format!("{err:#}");
+        if !self.should_send(workspace, &message, Instant::now()) {
+            return;
+        }
+        // `text` is what Slack incoming webhooks display; the rest is for generic receivers
+        let payload = serde_json::json!({
+            "text": format!("tend daemon: {workspace}: {message}"),
+            "workspace": workspace,
+            "error": message,
+            "cycle": cycle,
+        });
+        let result = self
+            .client
+            .post(&self.url)
+            .json(&payload)
+            .send()
+            .await
+            .and_then(|resp| resp.error_for_status());
+        if let Err(e) = result {
+            display::errln!("daemon: webhook notification failed: {e}");
+        }
+    }
+}
+
+/// Print a workspace error and forward it to the webhook, if one is configured.
+async fn report_error(workspace: &str, err: &anyhow::Error, cycle: u64, notifier: Option<&ErrorNotifier>) {
+    display::print_daemon_error(workspace, err);
+    if let Some(notifier) = notifier {
+        notifier.notify(workspace, err, cycle).await;
+    }
+}
+
+/// Set by the first ctrl-c/SIGTERM; long-running loops stop between units of work.
+static SHUTDOWN: AtomicBool = AtomicBool::new(false);
+
+/// Whether the daemon has been asked to stop after the in-flight cycle.
+/// Always false outside the daemon.
+pub(crate) fn shutdown_requested() -> bool {
+    SHUTDOWN.load(Ordering::Relaxed)
+}
+
+/// Wait for ctrl-c, or SIGTERM on unix (what systemd sends on stop).
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        if let Ok(mut term) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = term.recv() => {}
+            }
+            return;
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// First signal requests a graceful stop; a second one exits immediately.
+fn spawn_signal_handler(wake: Arc<Notify>) {
+    tokio::spawn(async move {
+        loop {
+            shutdown_signal().await;
+            if SHUTDOWN.swap(true, Ordering::Relaxed) {
+                display::errln!("daemon: second signal, exiting now");
+                std::process::exit(130);
+            }
+            display::errln!("daemon: shutting down after current cycle (signal again to force)");
+            wake.notify_one();
+        }
+    });
 }
 
 /// Run the daemon loop: sync + fetch + watch on interval, re-reading config each cycle.
 ///
-/// Workspaces are processed in parallel using tokio tasks.
+/// Workspaces are processed in parallel using tokio tasks. A shutdown signal lets
+/// the in-flight cycle finish its current repo before the loop exits.
 pub async fn run(opts: DaemonOpts) -> Result<()> {
     let mut cycle = 0u64;
+    let mut notifier: Option<Arc<ErrorNotifier>> = None;
+    let api_limiter = Arc::new(Semaphore::new(opts.api_jobs));
+    let wake = Arc::new(Notify::new());
+    spawn_signal_handler(Arc::clone(&wake));
+
+    let state = health::SharedState::default();
+    if let Some(addr) = opts.health_addr {
+        let listener = health::bind(addr).await?;
+        let max_quiet = health::liveness_window(opts.interval);
+        tokio::spawn(health::serve(listener, Arc::clone(&state), max_quiet));
+    }
 
-    loop {
+    while !shutdown_requested() {
         cycle += 1;
+        health::update(&state, |s| s.cycle_started(cycle));
 
         // Re-read config each cycle so nix rebuild changes are picked up
-        let cfg = match load_config(opts.config.as_deref()) {
+        let cfg = match load_config(opts.config.as_deref(), true) {
             Ok(c) => c,
             Err(e) => {
-                eprintln!("daemon: failed to load config: {e}");
+                display::errln!("daemon: failed to load config: {e}");
+                health::update(&state, |s| s.config_error = Some(format!("{e:#}")));
+                if let Some(ref notifier) = notifier {
+                    notifier.notify("config", &e, cycle).await;
+                }
                 tokio::select! {
                     _ = tokio::time::sleep(Duration::from_secs(opts.interval)) => continue,
-                    _ = tokio::signal::ctrl_c() => break,
+                    _ = wake.notified() => break,
                 }
             }
         };
 
-        let workspaces = filter_workspaces(&cfg.workspaces, opts.workspace.as_deref());
+        health::update(&state, |s| s.config_error = None);
+
+        // The webhook can change with the config; keep the notifier (and its
+        // rate-limit history) while the URL stays the same
+        match opts.notify_webhook.as_ref().or(cfg.notify_webhook.as_ref()) {
+            None => notifier = None,
+            Some(url) if notifier.as_ref().is_some_and(|n| &n.url == url) => {}
+            Some(url) => {
+                notifier = match ErrorNotifier::new(url) {
+                    Ok(n) => Some(Arc::new(n)),
+                    Err(e) => {
+                        display::errln!("daemon: webhook disabled: {e:#}");
+                        None
+                    }
+                };
+            }
+        }
+
+        let workspaces = opts.filter.select(&cfg.workspaces);
         let ws_count = workspaces.len();
+        let maintain = opts.maintenance_every.is_some_and(|every| cycle.is_multiple_of(every));
 
         if !opts.quiet {
             display::print_daemon_cycle_start(cycle);
@@ -47,32 +204,39 @@ pub async fn run(opts: DaemonOpts) -> Result<()> {
             let ws = ws.clone();
             let fetch = opts.fetch;
             let quiet = opts.quiet;
+            let api_limiter = Arc::clone(&api_limiter);
+            let notifier = notifier.clone();
+            let state = Arc::clone(&state);
             tasks.spawn(async move {
-                let name = ws.name.clone();
-                match run_workspace_cycle(&ws, fetch, quiet).await {
-                    Ok(()) => {}
-                    Err(e) => {
-                        display::print_daemon_error(&name, &e);
-                    }
+                let notifier = notifier.as_deref();
+                let result = run_workspace_cycle(&ws, fetch, maintain, quiet, &api_limiter, cycle, notifier).await;
+                if let Err(ref e) = result {
+                    report_error(&ws.name, e, cycle, notifier).await;
                 }
+                health::update(&state, |s| s.record(&ws.name, cycle, result.map_err(|e| format!("{e:#}"))));
             });
         }
 
         // Await all workspace tasks
         while let Some(result) = tasks.join_next().await {
             if let Err(e) = result {
-                eprintln!("daemon: workspace task panicked: {e}");
+                display::errln!("daemon: workspace task panicked: {e}");
             }
         }
 
+        health::update(&state, |s| s.cycle_finished());
+
         if !opts.quiet {
             display::print_daemon_cycle_done(cycle, ws_count);
             display::print_daemon_sleeping(opts.interval);
         }
 
+        if shutdown_requested() {
+            break;
+        }
         tokio::select! {
             _ = tokio::time::sleep(Duration::from_secs(opts.interval)) => {}
-            _ = tokio::signal::ctrl_c() => break,
+            _ = wake.notified() => break,
         }
     }
 
@@ -82,20 +246,41 @@ pub async fn run(opts: DaemonOpts) -> Result<()> {
 async fn run_workspace_cycle(
     ws: &crate::config::Workspace,
     fetch: bool,
+    maintain: bool,
     quiet: bool,
+    api_limiter: &Semaphore,
+    cycle: u64,
+    notifier: Option<&ErrorNotifier>,
 ) -> Result<()> {
-    let repos = sync::resolve_repos(ws, false).await?;
-    let (cloned, present) = sync::sync_repos(ws, &repos, quiet).await?;
+    // Skip this cycle if a manual tend run is mutating the same workspace
+    let Some(_lock) = lock::WorkspaceLock::try_acquire(&ws.name)? else {
+        display::print_workspace_locked(&ws.name);
+        return Ok(());
+    };
+
+    // Always rediscover: the cycle interval is shorter than the cache TTL, and the
+    // fresh result re-warms the cache for interactive commands and the watch step
+    let repos = sync::resolve_repos(ws, true, api_limiter).await?;
+    let outcome = sync::sync_repos(ws, &repos, sync::SyncOpts { quiet, ..Default::default() }).await?;
 
-    if !quiet || cloned > 0 {
-        display::print_sync_summary(&ws.name, cloned, present);
+    display::print_sync_summary(&ws.name, outcome.cloned, outcome.present);
+
+    if shutdown_requested() {
+        return Ok(());
     }
 
     if fetch {
         let (fetched, skipped) = sync::fetch_repos(ws, &repos, quiet).await?;
-        if !quiet {
-            display::print_fetch_summary(&ws.name, fetched, skipped);
-        }
+        display::print_fetch_summary(&ws.name, fetched, skipped);
+    }
+
+    if maintain && !shutdown_requested() {
+        let outcome = sync::maintain_repos(ws, &repos, quiet).await?;
+        display::print_maintenance_summary(&ws.name, &outcome);
+    }
+
+    if shutdown_requested() {
+        return Ok(());
     }
 
     // Watch: detect new versions if enabled
@@ -107,26 +292,21 @@ async fn run_workspace_cycle(
             let git_ops = git::SystemGitOps;
             let audit = crate::audit::AuditLog::default_path();
 
-            match watch::run_watch_cycle(ws, quiet, &gh, &cache_store, &matrix_appender, &git_ops, &audit).await {
+            match watch::run_watch_cycle(ws, quiet, &gh, &cache_store, &matrix_appender, &git_ops, &audit, api_limiter).await {
                 Ok(summary) => {
                     if !quiet {
                         display::print_watch_summary(&ws.name, &summary);
                     }
                 }
-                Err(e) => {
-                    display::print_daemon_error(&ws.name, &e);
-                }
+                Err(e) => report_error(&ws.name, &e, cycle, notifier).await,
             }
         }
 
         // Nix audit: run convergence loop if enabled
         if let Some(ref audit_cfg) = watch_cfg.nix_audit {
             if audit_cfg.enable {
-                match run_nix_audit_cycle(ws, audit_cfg, quiet).await {
-                    Ok(()) => {}
-                    Err(e) => {
-                        display::print_daemon_error(&ws.name, &e);
-                    }
+                if let Err(e) = run_nix_audit_cycle(ws, audit_cfg, quiet).await {
+                    report_error(&ws.name, &e, cycle, notifier).await;
                 }
             }
         }
@@ -179,16 +359,14 @@ async fn run_nix_audit_cycle(
             }
             audit.nix_audit_completed(total_repos, passing_repos, total_findings);
             if !quiet {
-                eprintln!(
+                display::errln!(
                     "  nix-audit: {passing_repos}/{total_repos} repos passing, {total_findings} findings"
                 );
             }
             (total_repos, passing_repos, total_findings)
         }
         Err(e) => {
-            if !quiet {
-                eprintln!("  nix-audit: check failed: {e}");
-            }
+            display::errln!("  nix-audit: check failed: {e}");
             return Ok(());
         }
     };
@@ -220,8 +398,8 @@ async fn run_nix_audit_cycle(
             .await;
 
         if let Ok(output) = fix_output {
-            if !quiet && !output.status.success() {
-                eprintln!("  nix-audit: fix returned non-zero");
+            if !output.status.success() {
+                display::errln!("  nix-audit: fix returned non-zero");
             }
         }
     }
@@ -263,3 +441,21 @@ async fn run_hook(hook: &crate::config::PostHook, audit: &crate::audit::AuditLog
 
     audit.hook_executed(&hook.trigger, &hook.command, exit_code, duration_ms);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notifier_suppresses_repeats_within_cooldown() {
+        let notifier = ErrorNotifier::new("http://127.0.0.1:9/hook").unwrap();
+        let start = Instant::now();
+
+        assert!(notifier.should_send("ws", "clone failed", start));
+        assert!(!notifier.should_send("ws", "clone failed", start + Duration::from_secs(60)));
+        // A different error or workspace is not suppressed
+        assert!(notifier.should_send("ws", "fetch failed", start));
+        assert!(notifier.should_send("other", "clone failed", start));
+        assert!(notifier.should_send("ws", "clone failed", start + NOTIFY_COOLDOWN));
+    }
+}
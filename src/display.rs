@@ -1,8 +1,289 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
 use colored::Colorize;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 
-use crate::sync::{RepoEntry, RepoStatus};
+use crate::flake;
+use crate::sync::{CloneFailure, RepoEntry, RepoStatus};
 use crate::watch;
 
+/// How much tend prints. `Quiet` drops informational stdout (progress,
+/// summaries) but keeps warnings and errors on stderr; `Silent` drops those
+/// too, leaving the exit code. `Verbose` adds diagnostics such as the API
+/// usage summary. A command's requested output (repo lists, status tables,
+/// JSON) is not informational and prints at every level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    Silent,
+    Quiet,
+    Normal,
+    Verbose,
+}
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(Verbosity::Normal as u8);
+
+/// Set once from `--quiet`/`--silent`/`--verbose` before any command runs.
+pub fn set_verbosity(verbosity: Verbosity) {
+    VERBOSITY.store(verbosity as u8, Ordering::Relaxed);
+}
+
+pub fn verbosity() -> Verbosity {
+    match VERBOSITY.load(Ordering::Relaxed) {
+        0 => Verbosity::Silent,
+        1 => Verbosity::Quiet,
+        2 => Verbosity::Normal,
+        _ => Verbosity::Verbose,
+    }
+}
+
+/// `println!` for informational output; dropped under `--quiet` and `--silent`.
+macro_rules! outln {
+    () => {
+        if $crate::display::verbosity() >= $crate::display::Verbosity::Normal {
+            $crate::display::write_line(false, String::new());
+        }
+    };
+    ($($arg:tt)*) => {
+        if $crate::display::verbosity() >= $crate::display::Verbosity::Normal {
+            $crate::display::write_line(false, format!("{}{}", $crate::display::line_prefix(), format_args!($($arg)*)));
+        }
+    };
+}
+
+/// `eprintln!` for warnings and errors; dropped only under `--silent`.
+macro_rules! errln {
+    ($($arg:tt)*) => {
+        if $crate::display::verbosity() > $crate::display::Verbosity::Silent {
+            $crate::display::write_line(true, format!("{}{}", $crate::display::line_prefix(), format_args!($($arg)*)));
+        }
+    };
+}
+
+pub(crate) use {errln, outln};
+
+/// Lines a workspace printed while running under `--parallel-workspaces`,
+/// held so they print in one piece instead of interleaving with other workspaces.
+#[derive(Debug, Default)]
+pub struct OutputBuffer(Mutex<Vec<(bool, String)>>);
+
+impl OutputBuffer {
+    /// Print the held lines in the order they were written, to stderr or stdout as each was meant.
+    pub fn flush(&self) {
+        let lines = std::mem::take(&mut *self.0.lock().unwrap_or_else(|e| e.into_inner()));
+        for (stderr, line) in lines {
+            if stderr {
+                eprintln!("{line}");
+            } else {
+                println!("{line}");
+            }
+        }
+    }
+}
+
+tokio::task_local! {
+    static TASK_BUFFER: Arc<OutputBuffer>;
+}
+
+thread_local! {
+    static THREAD_BUFFER: RefCell<Option<Arc<OutputBuffer>>> = const { RefCell::new(None) };
+}
+
+/// The buffer `outln!`/`errln!` currently write to, if output is being held.
+pub fn current_buffer() -> Option<Arc<OutputBuffer>> {
+    TASK_BUFFER
+        .try_with(Arc::clone)
+        .ok()
+        .or_else(|| THREAD_BUFFER.with(|b| b.borrow().clone()))
+}
+
+/// Run `fut` with its output held in a new buffer, returned next to its result.
+pub async fn buffered<F: Future>(fut: F) -> (F::Output, Arc<OutputBuffer>) {
+    let buffer = Arc::new(OutputBuffer::default());
+    let output = TASK_BUFFER.scope(Arc::clone(&buffer), fut).await;
+    (output, buffer)
+}
+
+/// Run `f` with this thread's output going to `buffer`. Threads don't inherit
+/// the buffer of the task or thread that started them, so workers pass it on.
+pub fn with_buffer<R>(buffer: Option<Arc<OutputBuffer>>, f: impl FnOnce() -> R) -> R {
+    /// Restores the thread's previous buffer, even if `f` panics.
+    struct Restore(Option<Arc<OutputBuffer>>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            THREAD_BUFFER.with(|b| *b.borrow_mut() = self.0.take());
+        }
+    }
+    let _restore = Restore(THREAD_BUFFER.with(|b| b.replace(buffer)));
+    f()
+}
+
+/// Print a finished `outln!`/`errln!` line, or hold it in the current buffer.
+#[doc(hidden)]
+pub fn write_line(stderr: bool, line: String) {
+    match current_buffer() {
+        Some(buffer) => buffer.0.lock().unwrap_or_else(|e| e.into_inner()).push((stderr, line)),
+        None if stderr => eprintln!("{line}"),
+        None => println!("{line}"),
+    }
+}
+
+static TIMESTAMPS: AtomicBool = AtomicBool::new(false);
+
+/// Env var that turns on `--timestamps` (`1`, `true`, or `yes`).
+pub const TIMESTAMPS_ENV: &str = "TEND_TIMESTAMPS";
+
+/// Prefix `outln!`/`errln!` lines with the time. Set once from
+/// `--timestamps`/`TEND_TIMESTAMPS`; the daemon stamps its own cycle lines.
+pub fn set_timestamps(on: bool) {
+    TIMESTAMPS.store(on, Ordering::Relaxed);
+}
+
+/// Whether a `TEND_TIMESTAMPS` value asks for timestamps.
+pub fn timestamps_from_env(value: Option<&str>) -> bool {
+    value.is_some_and(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+}
+
+/// The local time as the daemon prints it: `2024-05-01 12:00:00`.
+fn timestamp() -> String {
+    chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// `[<timestamp>] ` when timestamps are on, else empty.
+pub fn line_prefix() -> String {
+    if TIMESTAMPS.load(Ordering::Relaxed) {
+        format!("[{}] ", timestamp())
+    } else {
+        String::new()
+    }
+}
+
+/// Per-repo progress sink for sync/fetch.
+///
+/// On an interactive stderr each in-flight repo gets its own spinner line that is
+/// cleared when the repo finishes. Otherwise it falls back to plain lines, or
+/// nothing at all when quiet.
+pub struct RepoProgress {
+    multi: Option<&'static MultiProgress>,
+    quiet: bool,
+}
+
+/// One shared draw target so concurrent workspaces (daemon) stack their lines.
+static MULTI_PROGRESS: std::sync::OnceLock<MultiProgress> = std::sync::OnceLock::new();
+
+/// An in-flight repo's spinner; cleared on drop.
+pub struct RepoProgressLine(Option<ProgressBar>);
+
+impl RepoProgressLine {
+    /// Replace the spinner's text (no-op off a TTY).
+    pub fn set_message(&self, msg: String) {
+        if let Some(ref bar) = self.0 {
+            bar.set_message(msg);
+        }
+    }
+}
+
+impl RepoProgress {
+    pub fn new(quiet: bool) -> Self {
+        // Held output prints later as plain lines; a spinner would show now
+        let multi = (!quiet && current_buffer().is_none() && std::io::stderr().is_terminal())
+            .then(|| MULTI_PROGRESS.get_or_init(MultiProgress::new));
+        Self { multi, quiet }
+    }
+
+    /// Show a spinner for a repo until the returned line is dropped (TTY only).
+    pub fn spinner(&self, msg: String) -> RepoProgressLine {
+        let Some(multi) = self.multi else {
+            return RepoProgressLine(None);
+        };
+        let bar = multi.add(ProgressBar::new_spinner());
+        bar.set_style(
+            ProgressStyle::with_template("  {spinner} {msg}")
+                .unwrap_or_else(|_| ProgressStyle::default_spinner()),
+        );
+        bar.set_message(msg);
+        bar.enable_steady_tick(Duration::from_millis(100));
+        RepoProgressLine(Some(bar))
+    }
+
+    /// Print a per-repo line in plain mode; spinners replace these on a TTY.
+    pub fn note(&self, line: String) {
+        if self.multi.is_none() && !self.quiet {
+            outln!("  {line}");
+        }
+    }
+
+    /// Print a warning without tearing an active spinner.
+    pub fn warn(&self, line: String) {
+        match self.multi {
+            Some(multi) => multi.suspend(|| errln!("  warning: {line}")),
+            None => errln!("  warning: {line}"),
+        }
+    }
+}
+
+impl Drop for RepoProgressLine {
+    fn drop(&mut self) {
+        if let Some(ref bar) = self.0 {
+            bar.finish_and_clear();
+        }
+    }
+}
+
+/// Stable `<code> <workspace> <repo>` lines for scripts; no color, no summary.
+pub fn print_status_porcelain(workspace_name: &str, entries: &[RepoEntry]) {
+    for entry in entries {
+        println!("{} {} {}", entry.status.porcelain_code(), workspace_name, entry.name);
+    }
+}
+
+/// Order of the `tend status` table.
+#[derive(Debug, Clone, Copy, PartialEq, Default, clap::ValueEnum)]
+pub enum StatusSort {
+    /// Configured repos in name order, then unknown ones
+    #[default]
+    Name,
+    /// Most urgent first (missing, broken, dirty, ...), name order within a status
+    Status,
+}
+
+/// Layout of the human-readable `tend status` output.
+#[derive(Debug, Clone, Copy, PartialEq, Default, clap::ValueEnum)]
+pub enum StatusFormat {
+    /// A block per workspace with a fixed-width name column
+    #[default]
+    Compact,
+    /// One table across all workspaces, each column as wide as its widest cell
+    Table,
+}
+
+/// Icon, label, and color a status is shown with.
+fn status_style(status: &RepoStatus) -> (&'static str, &'static str, colored::Color) {
+    use colored::Color;
+
+    match status {
+        RepoStatus::Clean => ("ok", "clean", Color::Green),
+        RepoStatus::Dirty => ("!!", "dirty", Color::Yellow),
+        RepoStatus::Missing => ("--", "missing", Color::Red),
+        RepoStatus::Unknown => ("??", "unknown", Color::Cyan),
+        RepoStatus::Broken => ("xx", "broken", Color::Red),
+        RepoStatus::Detached => ("@@", "detached", Color::Magenta),
+        RepoStatus::Mirror => ("<>", "mirror", Color::Blue),
+        RepoStatus::InProgress(op) => ("~~", op.label(), Color::Red),
+    }
+}
+
+/// Reorder status entries for display. `Name` keeps `check_status`'s order.
+pub fn sort_entries(entries: &mut [RepoEntry], sort: StatusSort) {
+    if sort == StatusSort::Status {
+        entries.sort_by_key(|e| e.status.severity());
+    }
+}
+
 pub fn print_status(workspace_name: &str, entries: &[RepoEntry]) {
     let clean = entries
         .iter()
@@ -20,18 +301,42 @@ pub fn print_status(workspace_name: &str, entries: &[RepoEntry]) {
         .iter()
         .filter(|e| matches!(e.status, RepoStatus::Unknown))
         .count();
+    let broken = entries
+        .iter()
+        .filter(|e| matches!(e.status, RepoStatus::Broken))
+        .count();
+    let detached = entries
+        .iter()
+        .filter(|e| matches!(e.status, RepoStatus::Detached))
+        .count();
+    let mirrors = entries
+        .iter()
+        .filter(|e| matches!(e.status, RepoStatus::Mirror))
+        .count();
+    let in_progress = entries
+        .iter()
+        .filter(|e| matches!(e.status, RepoStatus::InProgress(_)))
+        .count();
 
     println!("{}", format!("workspace: {workspace_name}").bold());
     println!();
 
     for entry in entries {
-        let (icon, label) = match &entry.status {
-            RepoStatus::Clean => ("ok".green().to_string(), "clean"),
-            RepoStatus::Dirty => ("!!".yellow().to_string(), "dirty"),
-            RepoStatus::Missing => ("--".red().to_string(), "missing"),
-            RepoStatus::Unknown => ("??".cyan().to_string(), "unknown"),
+        let (icon, label, color) = status_style(&entry.status);
+        let icon = icon.color(color);
+        let stashed = match entry.stashes {
+            0 => String::new(),
+            n => format!(" ({n} stashed)").yellow().to_string(),
         };
-        println!("  [{icon}] {:<40} {label}", entry.name);
+        match entry.last_commit {
+            Some(ref when) => {
+                println!("  [{icon}] {:<40} {label:<8} {}{stashed}", entry.name, when.dimmed())
+            }
+            None => println!("  [{icon}] {:<40} {label}{stashed}", entry.name),
+        }
+        for change in &entry.changes {
+            println!("         {}", change.dimmed());
+        }
     }
 
     println!();
@@ -42,17 +347,111 @@ pub fn print_status(workspace_name: &str, entries: &[RepoEntry]) {
         missing.to_string().red(),
         unknown.to_string().cyan(),
     );
+    if broken > 0 {
+        println!(
+            "  {} broken (run `tend sync --repair` to re-clone)",
+            broken.to_string().red()
+        );
+    }
+    if detached > 0 {
+        println!(
+            "  {} detached (check out a branch before flake-update)",
+            detached.to_string().magenta()
+        );
+    }
+    if in_progress > 0 {
+        println!(
+            "  {} mid merge/rebase/bisect (finish or abort it before flake-update)",
+            in_progress.to_string().red()
+        );
+    }
+    if mirrors > 0 {
+        println!("  {} mirror/bare clones", mirrors.to_string().blue());
+    }
+}
+
+/// Cells of `status --format table`: a header row, then one row per repo.
+/// The last-commit and stash columns only appear when some repo has a value.
+pub fn status_table(workspaces: &[(String, Vec<RepoEntry>)]) -> Vec<Vec<String>> {
+    let entries = || workspaces.iter().flat_map(|(ws, entries)| entries.iter().map(move |e| (ws, e)));
+    let last_commit = entries().any(|(_, e)| e.last_commit.is_some());
+    let stashes = entries().any(|(_, e)| e.stashes > 0);
+
+    let mut header = vec!["WORKSPACE", "REPO", "STATUS"];
+    if last_commit {
+        header.push("LAST COMMIT");
+    }
+    if stashes {
+        header.push("STASHES");
+    }
+    let mut table = vec![header.into_iter().map(String::from).collect()];
+    for (ws, entry) in entries() {
+        let mut row = vec![ws.clone(), entry.name.clone(), status_style(&entry.status).1.to_string()];
+        if last_commit {
+            row.push(entry.last_commit.clone().unwrap_or_default());
+        }
+        if stashes {
+            row.push(match entry.stashes {
+                0 => String::new(),
+                n => n.to_string(),
+            });
+        }
+        table.push(row);
+    }
+    table
+}
+
+/// Width of each column: its widest cell, in characters.
+pub fn column_widths(table: &[Vec<String>]) -> Vec<usize> {
+    let mut widths = Vec::new();
+    for row in table {
+        widths.resize(widths.len().max(row.len()), 0);
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    widths
+}
+
+/// `status --format table`: every workspace in one aligned table, status
+/// colored, and changed files (`--show-changes`) listed under their repo.
+pub fn print_status_table(workspaces: &[(String, Vec<RepoEntry>)]) {
+    let table = status_table(workspaces);
+    let widths = column_widths(&table);
+    let entries: Vec<&RepoEntry> = workspaces.iter().flat_map(|(_, entries)| entries).collect();
+    for (i, row) in table.iter().enumerate() {
+        // Row 0 is the header
+        let entry = i.checked_sub(1).and_then(|n| entries.get(n).copied());
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .enumerate()
+            .map(|(col, (cell, &width))| {
+                // Leave the last column unpadded so lines don't end in spaces
+                let cell = if col + 1 == row.len() { cell.clone() } else { format!("{cell:<width$}") };
+                match entry {
+                    None => cell.bold().to_string(),
+                    Some(entry) if col == 2 => cell.color(status_style(&entry.status).2).to_string(),
+                    Some(_) => cell,
+                }
+            })
+            .collect();
+        println!("{}", cells.join("  ").trim_end());
+        for change in entry.iter().flat_map(|e| &e.changes) {
+            println!("{:indent$}{}", "", change.dimmed(), indent = widths[0] + 4);
+        }
+    }
 }
 
 pub fn print_sync_summary(workspace_name: &str, cloned: usize, present: usize) {
     if cloned == 0 {
-        println!(
+        outln!(
             "{}: all {} repos present",
             workspace_name.bold(),
             present
         );
     } else {
-        println!(
+        outln!(
             "{}: cloned {} new, {} already present",
             workspace_name.bold(),
             cloned.to_string().green(),
@@ -61,6 +460,68 @@ pub fn print_sync_summary(workspace_name: &str, cloned: usize, present: usize) {
     }
 }
 
+pub fn print_archive_summary(workspace_name: &str, outcome: &crate::sync::ArchiveOutcome) {
+    for name in &outcome.archived {
+        outln!("  [{}] {} moved to .archive/", "->".cyan(), name);
+    }
+    for (from, to) in &outcome.planned {
+        outln!(
+            "  [{}] would move {} to {} (not in config, clean)",
+            "->".cyan(),
+            from.display(),
+            to.display()
+        );
+    }
+    for name in &outcome.dirty {
+        errln!(
+            "  {}: {} is no longer in config but has uncommitted changes, leaving it in place",
+            "warning".yellow().bold(),
+            name.bold()
+        );
+    }
+    if !outcome.planned.is_empty() {
+        outln!("{}: would archive {} repos", workspace_name.bold(), outcome.planned.len());
+    } else if !outcome.archived.is_empty() {
+        outln!("{}: archived {} repos", workspace_name.bold(), outcome.archived.len());
+    }
+}
+
+/// Summary line for `sync --reconcile`. The dirty repos themselves are
+/// listed by `print_archive_summary`.
+pub fn print_reconcile_summary(workspace_name: &str, cloned: usize, archive: &crate::sync::ArchiveOutcome) {
+    if archive.dirty.is_empty() && !archive.planned.is_empty() {
+        outln!(
+            "{}: reconciled, cloned {}, would archive {}",
+            workspace_name.bold(),
+            cloned.to_string().green(),
+            archive.planned.len().to_string().cyan()
+        );
+    } else if archive.dirty.is_empty() {
+        outln!(
+            "{}: reconciled, cloned {}, archived {}",
+            workspace_name.bold(),
+            cloned.to_string().green(),
+            archive.archived.len().to_string().cyan()
+        );
+    } else {
+        errln!(
+            "{}: {}: {} repo(s) not in config have uncommitted changes; nothing cloned or archived",
+            "error".red().bold(),
+            workspace_name.bold(),
+            archive.dirty.len()
+        );
+    }
+}
+
+/// Print every failed clone across workspaces, one per line with git's first error line.
+pub fn print_clone_failures(failures: &[(String, CloneFailure)]) {
+    errln!("{}", "failed clones:".red().bold());
+    for (workspace, failure) in failures {
+        let reason = failure.error.lines().next().unwrap_or("unknown error");
+        errln!("  {}/{}: {}", workspace, failure.repo.bold(), reason);
+    }
+}
+
 pub fn print_repo_list(workspace_name: &str, repos: &[String]) {
     println!("{} ({} repos):", workspace_name.bold(), repos.len());
     for repo in repos {
@@ -68,6 +529,127 @@ pub fn print_repo_list(workspace_name: &str, repos: &[String]) {
     }
 }
 
+/// `list --diff` output: `+ repo` to clone, `- repo` on disk but not in config.
+pub fn print_repo_drift(workspace_name: &str, drift: &[(char, &str)]) {
+    if drift.is_empty() {
+        println!("{}: matches config", workspace_name.bold());
+        return;
+    }
+    println!("{}:", workspace_name.bold());
+    for (sign, repo) in drift {
+        match sign {
+            '+' => println!("  {}", format!("+ {repo}").green()),
+            _ => println!("  {}", format!("- {repo}").red()),
+        }
+    }
+}
+
+/// Clones whose remote doesn't match the config, as found by `tend verify`.
+pub fn print_remote_mismatches(workspace_name: &str, remote: &str, mismatches: &[crate::sync::RemoteMismatch]) {
+    if mismatches.is_empty() {
+        println!("{}: remotes match config", workspace_name.bold());
+        return;
+    }
+    println!("{}:", workspace_name.bold());
+    for m in mismatches {
+        let actual = match m.actual {
+            Some(ref url) => url.red().to_string(),
+            None => format!("no {remote} remote").red().to_string(),
+        };
+        let fixed = if m.fixed { format!(" {}", "fixed".green()) } else { String::new() };
+        println!("  {:<30} {actual} (expected {}){fixed}", m.repo, m.expected);
+    }
+}
+
+/// Per-workspace disk usage, largest first. Each row is
+/// `(workspace_name, base_dir, present_repos, bytes)`; workspaces sharing a
+/// base_dir are counted once in the total.
+pub fn print_disk_usage(rows: &mut [(String, String, usize, u64)]) {
+    rows.sort_by(|a, b| b.3.cmp(&a.3).then_with(|| a.0.cmp(&b.0)));
+    let mut seen_dirs = std::collections::HashSet::new();
+    let mut total_bytes = 0;
+    let mut total_repos = 0;
+    for (name, base_dir, repos, bytes) in rows.iter() {
+        println!("  {:>10}  {:<30} {repos} repos", format_size(*bytes), name.bold());
+        if seen_dirs.insert(base_dir.as_str()) {
+            total_bytes += bytes;
+        }
+        total_repos += repos;
+    }
+    println!(
+        "  {:>10}  {:<30} {total_repos} repos",
+        format_size(total_bytes).bold(),
+        "total".bold()
+    );
+}
+
+/// Binary-prefixed size, e.g. `1.5 GiB`.
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+/// Render workspaces as a tree grouped by `base_dir`.
+/// Each item is `(base_dir, workspace_name, repos)`; groups keep first-seen order.
+pub fn print_repo_tree(workspaces: &[(String, String, Vec<String>)]) {
+    let mut groups: Vec<(&str, Vec<usize>)> = Vec::new();
+    for (idx, (base_dir, _, _)) in workspaces.iter().enumerate() {
+        match groups.iter_mut().find(|(dir, _)| dir == base_dir) {
+            Some((_, members)) => members.push(idx),
+            None => groups.push((base_dir, vec![idx])),
+        }
+    }
+
+    for (base_dir, members) in &groups {
+        println!("{}", base_dir.bold());
+        for (i, &idx) in members.iter().enumerate() {
+            let (_, name, repos) = &workspaces[idx];
+            let last_ws = i + 1 == members.len();
+            let (branch, indent) = if last_ws { ("└── ", "    ") } else { ("├── ", "│   ") };
+            println!("{}{} ({} repos)", branch.dimmed(), name.bold(), repos.len());
+            for (j, repo) in repos.iter().enumerate() {
+                let leaf = if j + 1 == repos.len() { "└── " } else { "├── " };
+                println!("{}{}{repo}", indent.dimmed(), leaf.dimmed());
+            }
+        }
+    }
+}
+
+pub fn print_rename_edits(old: &str, new: &str, edits: &[String], dry_run: bool) {
+    if edits.is_empty() {
+        println!("no references to {} in config", old.bold());
+        return;
+    }
+    let verb = if dry_run { "would rename" } else { "renamed" };
+    println!("{verb} {} -> {} in:", old.bold(), new.bold());
+    for edit in edits {
+        println!("  {edit}");
+    }
+}
+
+/// `made N API requests, M rate-limit remaining`, on stderr so it never mixes
+/// into a command's own output. Nothing is printed when no request was made.
+pub fn print_api_usage(usage: &crate::provider::ApiUsage) {
+    if usage.requests == 0 {
+        return;
+    }
+    let remaining = match usage.remaining {
+        Some(remaining) => format!(", {remaining} rate-limit remaining"),
+        None => String::new(),
+    };
+    errln!("made {} API requests{remaining}", usage.requests);
+}
+
 pub fn print_discover_results(org: &str, repos: &[String]) {
     println!(
         "discovered {} repos in {}:",
@@ -79,9 +661,72 @@ pub fn print_discover_results(org: &str, repos: &[String]) {
     }
 }
 
+pub fn print_token_info(info: &crate::provider::TokenInfo) {
+    use crate::provider::TokenInfo;
+
+    match info {
+        TokenInfo::Unauthenticated => {
+            println!(
+                "{} no token found (set TEND_GITHUB_TOKEN or GITHUB_TOKEN)",
+                "unauthenticated:".yellow().bold()
+            );
+            println!("  only public repos are visible; API limit is 60 requests/hour");
+        }
+        TokenInfo::Authenticated { login, scopes } => {
+            println!("{} {}", "authenticated as".green(), login.bold());
+            match scopes {
+                Some(scopes) if scopes.is_empty() => println!("  scopes: (none)"),
+                Some(scopes) => println!("  scopes: {}", scopes.join(", ")),
+                None => println!("  scopes: not reported (fine-grained token — check repository access in GitHub settings)"),
+            }
+            let missing = info.missing_scopes();
+            if !missing.is_empty() {
+                println!(
+                    "  {} missing scope(s): {} — private repos will not be discovered or cloned",
+                    "insufficient:".red().bold(),
+                    missing.join(", ")
+                );
+            }
+        }
+    }
+}
+
+pub fn print_rate_limits(limits: &crate::provider::RateLimits) {
+    if limits.authenticated {
+        println!("{} (token limits, 5000 requests/hour core)", "authenticated".green());
+    } else {
+        println!(
+            "{} (no token: 60 requests/hour core; set TEND_GITHUB_TOKEN or GITHUB_TOKEN)",
+            "unauthenticated".yellow().bold()
+        );
+    }
+    for (name, bucket) in [("core", &limits.core), ("search", &limits.search)] {
+        let remaining = format!("{}/{}", bucket.remaining, bucket.limit);
+        // Red under 10% left, yellow under half
+        let remaining = if bucket.remaining * 10 < bucket.limit {
+            remaining.red().bold()
+        } else if bucket.remaining * 2 < bucket.limit {
+            remaining.yellow()
+        } else {
+            remaining.green()
+        };
+        let reset = match chrono::DateTime::from_timestamp(bucket.reset, 0) {
+            Some(at) => {
+                let mins = (at - chrono::Utc::now()).num_minutes().max(0);
+                format!(
+                    "resets {} (in {mins}m)",
+                    at.with_timezone(&chrono::Local).format("%H:%M:%S")
+                )
+            }
+            None => "reset time unknown".to_string(),
+        };
+        println!("  {name:<8} {remaining:<12} {}", reset.dimmed());
+    }
+}
+
 pub fn print_daemon_cycle_start(cycle: u64) {
-    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
-    println!(
+    let now = timestamp();
+    outln!(
         "[{}] {} cycle {}",
         now,
         "daemon:".bold(),
@@ -90,8 +735,8 @@ pub fn print_daemon_cycle_start(cycle: u64) {
 }
 
 pub fn print_daemon_cycle_done(cycle: u64, workspaces: usize) {
-    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
-    println!(
+    let now = timestamp();
+    outln!(
         "[{}] {} cycle {} done ({} workspaces)",
         now,
         "daemon:".bold(),
@@ -104,7 +749,7 @@ pub fn print_fetch_summary(workspace_name: &str, fetched: usize, skipped: usize)
     if fetched == 0 && skipped == 0 {
         return;
     }
-    println!(
+    outln!(
         "{}: fetched {}, skipped {}",
         workspace_name.bold(),
         fetched.to_string().green(),
@@ -112,9 +757,19 @@ pub fn print_fetch_summary(workspace_name: &str, fetched: usize, skipped: usize)
     );
 }
 
+pub fn print_maintenance_summary(workspace_name: &str, outcome: &crate::sync::MaintenanceOutcome) {
+    outln!(
+        "{}: maintained {}, skipped {} dirty, {} failed",
+        workspace_name.bold(),
+        outcome.maintained.to_string().green(),
+        outcome.dirty.to_string().yellow(),
+        outcome.failed.to_string().red(),
+    );
+}
+
 pub fn print_daemon_error(workspace_name: &str, err: &anyhow::Error) {
-    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
-    eprintln!(
+    let now = timestamp();
+    errln!(
         "[{}] {}: {} {}",
         now,
         "error".red().bold(),
@@ -123,9 +778,63 @@ pub fn print_daemon_error(workspace_name: &str, err: &anyhow::Error) {
     );
 }
 
+pub fn print_workspace_error(workspace_name: &str, err: &anyhow::Error) {
+    errln!("{}: {}: {err:#}", "error".red().bold(), workspace_name.bold());
+}
+
+pub fn print_duplicate_repos(workspace_name: &str, repos: &[String]) {
+    errln!(
+        "{}: {}: skipping {} repo(s) already handled by another workspace with the same base_dir: {}",
+        "warning".yellow().bold(),
+        workspace_name.bold(),
+        repos.len(),
+        repos.join(", ")
+    );
+}
+
+pub fn print_empty_workspace(workspace_name: &str) {
+    errln!(
+        "{}: workspace {}: no repos match (check the org, repo filters, and excludes)",
+        "warning".yellow().bold(),
+        workspace_name.bold()
+    );
+}
+
+/// `tend dirty`: one line per repo with uncommitted work, by full path, so
+/// the output can be pasted straight into `cd`.
+pub fn print_uncommitted(workspace_name: &str, found: &[(std::path::PathBuf, RepoEntry)]) {
+    if found.is_empty() {
+        return;
+    }
+    println!("{}", format!("workspace: {workspace_name}").bold());
+    for (path, entry) in found {
+        let (_, label, color) = status_style(&entry.status);
+        println!("  {} {}", format!("{label:<8}").color(color), path.display());
+        for change in &entry.changes {
+            println!("           {}", change.dimmed());
+        }
+    }
+}
+
+pub fn print_post_sync_hook_failed(workspace_name: &str, status: &std::process::ExitStatus, stderr: &str) {
+    errln!(
+        "{}: workspace {}: post_sync_hook failed ({status}): {stderr}",
+        "warning".yellow().bold(),
+        workspace_name.bold()
+    );
+}
+
+pub fn print_workspace_locked(workspace_name: &str) {
+    errln!(
+        "{}: workspace {} is locked by another tend process, skipping",
+        "warning".yellow().bold(),
+        workspace_name.bold()
+    );
+}
+
 pub fn print_daemon_sleeping(interval: u64) {
-    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
-    println!(
+    let now = timestamp();
+    outln!(
         "[{}] {} sleeping {}s",
         now,
         "daemon:".bold(),
@@ -134,22 +843,77 @@ pub fn print_daemon_sleeping(interval: u64) {
 }
 
 pub fn print_flake_chain_header(workspace_name: &str, changed: &str, steps: &[crate::flake::UpdateStep]) {
-    println!("{}", format!("workspace: {workspace_name}").bold());
-    println!("  changed: {}", changed.cyan());
-    println!("  chain ({} steps):", steps.len().to_string().green());
+    outln!("{}", format!("workspace: {workspace_name}").bold());
+    outln!("  changed: {}", changed.cyan());
+    outln!("  chain ({} steps):", steps.len().to_string().green());
     for (i, step) in steps.iter().enumerate() {
-        println!(
+        outln!(
             "    {}. {} → nix flake update {}",
             i + 1,
             step.repo.bold(),
             step.inputs.join(" ")
         );
     }
-    println!();
+    outln!();
+}
+
+/// `flake-status` report, upstream repos first. A stale input whose repo is
+/// itself behind is marked: updating it now would still leave it behind.
+pub fn print_flake_status(workspace_name: &str, statuses: &[crate::flake::RepoFlakeStatus]) {
+    println!("{}:", workspace_name.bold());
+    let behind: Vec<&str> = statuses
+        .iter()
+        .filter(|s| !s.stale.is_empty())
+        .map(|s| s.repo.as_str())
+        .collect();
+    for status in statuses {
+        if status.no_lock {
+            println!("  {} {}", status.repo.bold(), "no flake.lock".dimmed());
+            continue;
+        }
+        if status.stale.is_empty() {
+            println!("  {} {}", status.repo.bold(), "up to date".green());
+        } else {
+            println!("  {} {}", status.repo.bold(), format!("{} stale input(s)", status.stale.len()).yellow());
+        }
+        for stale in &status.stale {
+            let note = if behind.contains(&stale.input.as_str()) {
+                format!(" ({} is itself behind)", stale.input)
+            } else {
+                String::new()
+            };
+            println!(
+                "    {} {} → {}{}",
+                stale.input,
+                short_rev(&stale.locked).dimmed(),
+                short_rev(&stale.upstream).cyan(),
+                note.yellow()
+            );
+        }
+        for (input, reason) in &status.unchecked {
+            println!("    {} {}", input, format!("not checked: {reason}").dimmed());
+        }
+    }
+}
+
+/// First 7 characters of a commit hash.
+fn short_rev(rev: &str) -> &str {
+    rev.get(..7).unwrap_or(rev)
+}
+
+pub fn print_flake_missing_inputs(workspace_name: &str, missing: &[crate::flake::MissingInput]) {
+    errln!(
+        "{}: {}: flake_deps names inputs these flakes don't declare:",
+        "error".red().bold(),
+        workspace_name.bold()
+    );
+    for m in missing {
+        errln!("  {} has no input {}", m.repo.bold(), m.input.yellow());
+    }
 }
 
 pub fn print_flake_step_start(step: usize, total: usize, repo: &str, inputs: &[String]) {
-    println!(
+    outln!(
         "  [{}/{}] {} nix flake update {}",
         step,
         total,
@@ -158,23 +922,72 @@ pub fn print_flake_step_start(step: usize, total: usize, repo: &str, inputs: &[S
     );
 }
 
+pub fn print_flake_step_resumed(step: usize, total: usize, repo: &str) {
+    outln!(
+        "  [{}/{}] {} {}",
+        step,
+        total,
+        repo.bold(),
+        "already done (resumed)".dimmed()
+    );
+}
+
 pub fn print_flake_step_done(repo: &str) {
-    println!("  [{}] {} committed and pushed", "ok".green(), repo);
+    outln!("  [{}] {} committed and pushed", "ok".green(), repo);
 }
 
 pub fn print_flake_step_dry_run() {
-    println!("  [{}] (dry-run, skipped)", ">>".yellow());
+    outln!("  [{}] (dry-run, skipped)", ">>".yellow());
+}
+
+pub fn print_flake_step_stash_warning(repo: &str, stashes: usize) {
+    errln!(
+        "  {}: {} has {} stash entries; they are left untouched",
+        "warning".yellow().bold(),
+        repo,
+        stashes
+    );
+}
+
+pub fn print_flake_lock_changes(repo: &str, changes: &[flake::LockChange]) {
+    println!("  {} flake.lock changes:", repo.bold());
+    let short = |rev: &str| rev.chars().take(12).collect::<String>();
+    for change in changes {
+        match (&change.old, &change.new) {
+            (Some(old), Some(new)) => println!(
+                "    {:<24} {} -> {}",
+                change.input,
+                short(old).red(),
+                short(new).green()
+            ),
+            (None, Some(new)) => println!("    {:<24} {} {}", change.input, "added".green(), short(new)),
+            (Some(old), None) => println!("    {:<24} {} {}", change.input, "removed".red(), short(old)),
+            (None, None) => {}
+        }
+    }
+    if changes.is_empty() {
+        println!("    {}", "(no input revisions changed)".dimmed());
+    }
+}
+
+pub fn print_flake_confirm_prompt(repo: &str) {
+    print!("  commit and push {repo}? [y/N] ");
+    let _ = std::io::Write::flush(&mut std::io::stdout());
+}
+
+pub fn print_flake_step_declined(repo: &str) {
+    outln!("  [{}] {} skipped, flake.lock restored", "--".yellow(), repo);
 }
 
 pub fn print_flake_step_no_changes(repo: &str) {
-    println!("  [{}] {} flake.lock unchanged", "==".cyan(), repo);
+    outln!("  [{}] {} flake.lock unchanged", "==".cyan(), repo);
 }
 
 pub fn print_flake_chain_complete(updated: usize) {
     if updated == 0 {
-        println!("\n  {}", "no repos needed updating".cyan());
+        outln!("\n  {}", "no repos needed updating".cyan());
     } else {
-        println!(
+        outln!(
             "\n  {} {} updated",
             "done:".green().bold(),
             updated.to_string().green()
@@ -182,9 +995,32 @@ pub fn print_flake_chain_complete(updated: usize) {
     }
 }
 
+pub fn print_flake_rollback_header(workspace_name: &str, journal: &crate::flake_journal::FlakeJournal) {
+    outln!("{}", format!("workspace: {workspace_name}").bold());
+    outln!(
+        "  rolling back chain from {} ({}, {} commits)",
+        journal.changed.cyan(),
+        journal.timestamp,
+        journal.entries.len()
+    );
+}
+
+/// Print the git commands that would undo one repo's journaled commit.
+pub fn print_flake_rollback_commands(repo: &str, repo_path: &std::path::Path, commands: &[&[&str]]) {
+    outln!("  [{}] {}", ">>".yellow(), repo.bold());
+    for args in commands {
+        outln!("      git -C {} {}", repo_path.display(), args.join(" "));
+    }
+}
+
+pub fn print_flake_rollback_done(repo: &str, before: &str) {
+    let short = &before[..before.len().min(12)];
+    outln!("  [{}] {} reset to {} and force-pushed", "ok".green(), repo, short);
+}
+
 pub fn print_watch_summary(workspace_name: &str, summary: &watch::WatchSummary) {
     if summary.new_versions == 0 && summary.file_changes == 0 && summary.flake_input_updates == 0 && summary.flake_refreshed == 0 {
-        println!(
+        outln!(
             "{}: watched {} repos, no new versions",
             workspace_name.bold(),
             summary.checked,
@@ -203,7 +1039,7 @@ pub fn print_watch_summary(workspace_name: &str, summary: &watch::WatchSummary)
         if summary.flake_refreshed > 0 {
             parts.push(format!("{} flake refreshed", summary.flake_refreshed.to_string().green()));
         }
-        println!(
+        outln!(
             "{}: watched {} repos, {} detected",
             workspace_name.bold(),
             summary.checked,
@@ -211,7 +1047,7 @@ pub fn print_watch_summary(workspace_name: &str, summary: &watch::WatchSummary)
         );
     }
     if summary.errors > 0 {
-        println!(
+        outln!(
             "  {} repos had errors",
             summary.errors.to_string().yellow(),
         );
@@ -219,7 +1055,7 @@ pub fn print_watch_summary(workspace_name: &str, summary: &watch::WatchSummary)
 }
 
 pub fn print_flake_refresh_skip(repo: &str, reason: &str) {
-    println!(
+    outln!(
         "  [{}] {} ({})",
         "--".cyan(),
         repo,
@@ -228,15 +1064,15 @@ pub fn print_flake_refresh_skip(repo: &str, reason: &str) {
 }
 
 pub fn print_flake_refresh_updated(repo: &str) {
-    println!("  [{}] {} refreshed and pushed", "ok".green(), repo.bold());
+    outln!("  [{}] {} refreshed and pushed", "ok".green(), repo.bold());
 }
 
 pub fn print_flake_refresh_no_changes(repo: &str) {
-    println!("  [{}] {} flake.lock unchanged", "==".cyan(), repo);
+    outln!("  [{}] {} flake.lock unchanged", "==".cyan(), repo);
 }
 
 pub fn print_flake_refresh_error(repo: &str, err: &str) {
-    eprintln!(
+    errln!(
         "  [{}] {} {}",
         "!!".red(),
         repo,
@@ -245,7 +1081,7 @@ pub fn print_flake_refresh_error(repo: &str, err: &str) {
 }
 
 pub fn print_watch_new_version(repo: &str, version: &str, tag: &str) {
-    println!(
+    outln!(
         "  [{}] {} {} (tag: {})",
         "new".green(),
         repo.bold(),
@@ -253,3 +1089,31 @@ pub fn print_watch_new_version(repo: &str, version: &str, tag: &str) {
         tag,
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_buffered_output_follows_worker_and_blocking_threads() {
+        let ((), buffer) = buffered(async {
+            outln!("start");
+            crate::sync::run_bounded(&[1, 2], 2, || false, |n| errln!("worker {n}"));
+            let held = current_buffer();
+            tokio::task::spawn_blocking(move || with_buffer(held, || outln!("blocking")))
+                .await
+                .unwrap();
+        })
+        .await;
+        assert!(current_buffer().is_none());
+
+        let mut lines = buffer.0.lock().unwrap().clone();
+        assert_eq!(lines.remove(0), (false, "start".to_string()));
+        assert_eq!(lines.pop(), Some((false, "blocking".to_string())));
+        lines.sort();
+        assert_eq!(lines, [(true, "worker 1".to_string()), (true, "worker 2".to_string())]);
+
+        buffer.flush();
+        assert!(buffer.0.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,124 @@
+use std::fmt;
+
+/// Failure categories with their own process exit codes, so scripts can tell
+/// "my config is broken" from "GitHub is down". Anything unclassified exits 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    /// The config file could not be read or parsed (2)
+    Config,
+    /// A provider API request failed (3)
+    Network,
+    /// A git subprocess failed (4)
+    Git,
+    /// The run finished, but some repos or workspaces failed (5)
+    PartialSync,
+}
+
+impl Failure {
+    pub fn code(self) -> u8 {
+        match self {
+            Failure::Config => 2,
+            Failure::Network => 3,
+            Failure::Git => 4,
+            Failure::PartialSync => 5,
+        }
+    }
+
+    /// Tag `err` with this category. The message and cause chain are unchanged.
+    pub fn tag(self, err: anyhow::Error) -> anyhow::Error {
+        anyhow::Error::new(Classified { failure: self, error: err })
+    }
+}
+
+/// `anyhow!` tagged as `Failure::Git`, for a git subprocess that failed:
+/// `return Err(git_err!("git push failed in {repo}: {stderr}"))`.
+macro_rules! git_err {
+    ($($arg:tt)*) => {
+        $crate::exit::Failure::Git.tag(anyhow::anyhow!($($arg)*))
+    };
+}
+
+pub(crate) use git_err;
+
+/// A plain nonzero exit (1) for a command that already printed its answer,
+/// like `dirty` finding uncommitted work. `main` prints no `Error:` line for it.
+#[derive(Debug)]
+pub struct Quiet;
+
+impl fmt::Display for Quiet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("exiting nonzero")
+    }
+}
+
+impl std::error::Error for Quiet {}
+
+/// Whether `err` is a `Quiet` exit, which reports nothing further.
+pub fn is_quiet(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<Quiet>().is_some()
+}
+
+/// An error carrying its `Failure` category; displays exactly as the wrapped error.
+struct Classified {
+    failure: Failure,
+    error: anyhow::Error,
+}
+
+impl fmt::Display for Classified {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl fmt::Debug for Classified {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.error, f)
+    }
+}
+
+impl std::error::Error for Classified {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.error.source()
+    }
+}
+
+/// Exit code for an error: the outermost tagged category, then any HTTP
+/// transport error in the chain as `Network`, otherwise 1.
+pub fn exit_code(err: &anyhow::Error) -> u8 {
+    if let Some(classified) = err.chain().find_map(|e| e.downcast_ref::<Classified>()) {
+        return classified.failure.code();
+    }
+    if err.chain().any(|e| e.downcast_ref::<reqwest::Error>().is_some()) {
+        return Failure::Network.code();
+    }
+    1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_exit_code_keeps_message_and_finds_tag_under_context() {
+        let err = Failure::Git.tag(anyhow::anyhow!("git push failed in lib: rejected"));
+        assert_eq!(err.to_string(), "git push failed in lib: rejected");
+        assert_eq!(exit_code(&err), 4);
+
+        let err = Err::<(), _>(err).context("updating lib").unwrap_err();
+        assert_eq!(format!("{err:#}"), "updating lib: git push failed in lib: rejected");
+        assert_eq!(exit_code(&err), 4);
+
+        let inner = Err::<(), _>(anyhow::anyhow!("bad yaml")).context("parsing config.yaml");
+        let err = Failure::Config.tag(inner.unwrap_err());
+        assert_eq!(format!("{err:#}"), "parsing config.yaml: bad yaml");
+        assert_eq!(exit_code(&err), 2);
+
+        assert_eq!(exit_code(&anyhow::anyhow!("plain")), 1);
+
+        let err = anyhow::Error::new(Quiet);
+        assert!(is_quiet(&err));
+        assert_eq!(exit_code(&err), 1);
+        assert!(!is_quiet(&anyhow::anyhow!("plain")));
+    }
+}
@@ -1,18 +1,105 @@
 use anyhow::{bail, Context, Result};
-use std::collections::{HashMap, HashSet, VecDeque};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::io::BufRead;
 use std::path::Path;
 use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-use crate::config::Workspace;
+use crate::config::{FlakeCommitGranularity, Workspace};
 use crate::display;
+use crate::exit::{git_err, Failure};
+use crate::flake_journal::{self, ChainProgress, FlakeJournal, JournalEntry};
+use crate::runner;
+use crate::sync::{self, ResolvedRepos};
+
+/// Maximum number of repos updated concurrently within one chain level.
+const PARALLEL_STEPS: usize = 4;
 
 /// A single step in the update chain.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct UpdateStep {
     /// Repo to update (directory name under base_dir)
     pub repo: String,
     /// Flake inputs to pass to `nix flake update`
     pub inputs: Vec<String>,
+    /// Topological level: steps sharing a level have no dependency between them
+    pub level: usize,
+}
+
+/// One workspace's chain, as printed by `flake-update --dry-run --json`.
+#[derive(Debug, Serialize)]
+pub struct UpdatePlan<'a> {
+    pub workspace: &'a str,
+    /// Repo whose push triggered the chain
+    pub changed: &'a str,
+    /// Steps in execution order (empty when nothing depends on `changed`)
+    pub steps: &'a [UpdateStep],
+}
+
+/// Options controlling how `execute_update_chain` runs.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ExecuteOpts<'a> {
+    /// Print the chain without running nix or git
+    pub dry_run: bool,
+    /// Suppress per-step output
+    pub quiet: bool,
+    /// Update one repo at a time instead of in parallel within a level
+    pub sequential: bool,
+    /// Refuse to update a repo unless it is on the remote's default branch
+    pub require_default_branch: bool,
+    /// Run `git fetch --unshallow` on shallow clones instead of refusing them
+    /// (taken from the workspace's `flake_auto_unshallow`)
+    pub auto_unshallow: bool,
+    /// Skip steps an interrupted run of the same chain already finished
+    pub resume: bool,
+    /// Show each repo's flake.lock changes and ask before committing
+    /// (steps then run one at a time)
+    pub interactive: bool,
+    /// One commit per repo or per input, overriding the workspace's
+    /// `flake_commit_granularity`
+    pub commit_granularity: Option<FlakeCommitGranularity>,
+    /// (name, email) to commit as, parsed from the workspace's `flake_commit_author`
+    pub commit_author: Option<(&'a str, &'a str)>,
+    /// Extra `nix flake update` arguments; the workspace's `flake_update_args`
+    /// are put in front of these
+    pub nix_args: &'a [String],
+}
+
+/// A flake input whose locked revision differs between two flake.lock versions.
+#[derive(Debug, PartialEq)]
+pub struct LockChange {
+    pub input: String,
+    /// Locked rev before the update (`None` for a new input)
+    pub old: Option<String>,
+    /// Locked rev after the update (`None` for a removed input)
+    pub new: Option<String>,
+}
+
+/// A `flake_deps` input that the repo's flake doesn't declare.
+#[derive(Debug, PartialEq)]
+pub struct MissingInput {
+    pub repo: String,
+    pub input: String,
+}
+
+/// A `flake_deps` input locked at an older revision than its upstream's current one.
+#[derive(Debug, PartialEq)]
+pub struct StaleInput {
+    pub input: String,
+    pub locked: String,
+    pub upstream: String,
+}
+
+/// How current one `flake_deps` repo's locked inputs are (`flake-status`).
+#[derive(Debug, Default, PartialEq)]
+pub struct RepoFlakeStatus {
+    pub repo: String,
+    /// The repo has no flake.lock (not cloned, or never locked)
+    pub no_lock: bool,
+    pub stale: Vec<StaleInput>,
+    /// Inputs whose upstream couldn't be resolved, with the reason
+    pub unchecked: Vec<(String, String)>,
 }
 
 /// Compute the ordered chain of repos to update after `changed` was pushed.
@@ -20,8 +107,11 @@ pub struct UpdateStep {
 /// Uses the `flake_deps` map (repo → list of inputs it depends on) to:
 /// 1. Build a reverse map (input → repos that depend on it)
 /// 2. BFS from `changed` to find all transitively affected repos
-/// 3. Topological sort (Kahn's) the affected repos
+/// 3. Topological sort (Kahn's) the affected repos, grouped into levels
 /// 4. For each repo, compute which inputs were updated earlier in the chain
+///
+/// Steps are returned ordered by level (then name); every step's inputs are
+/// produced by steps at strictly lower levels.
 pub fn compute_update_chain(
     changed: &str,
     flake_deps: &HashMap<String, Vec<String>>,
@@ -62,8 +152,9 @@ pub fn compute_update_chain(
         in_degree.entry(repo).or_insert(0);
         if let Some(deps) = flake_deps.get(repo) {
             for dep in deps {
-                // Only count edges from affected repos or the changed repo
-                if affected.contains(dep.as_str()) || dep == changed {
+                // Only count edges between affected repos; `changed` is already
+                // updated, so repos depending only on it start at level 0
+                if affected.contains(dep.as_str()) {
                     forward.entry(dep.as_str()).or_default().push(repo);
                     *in_degree.entry(repo).or_insert(0) += 1;
                 }
@@ -72,26 +163,33 @@ pub fn compute_update_chain(
     }
 
     let mut sorted: Vec<&str> = Vec::new();
-    let mut topo_queue: VecDeque<&str> = VecDeque::new();
+    let mut level_of: HashMap<&str, usize> = HashMap::new();
+    let mut current: Vec<&str> = in_degree
+        .iter()
+        .filter(|(_, &deg)| deg == 0)
+        .map(|(&repo, _)| repo)
+        .collect();
+    let mut level = 0usize;
 
-    for (&repo, &deg) in &in_degree {
-        if deg == 0 {
-            topo_queue.push_back(repo);
-        }
-    }
-
-    while let Some(repo) = topo_queue.pop_front() {
-        sorted.push(repo);
-        if let Some(dependents) = forward.get(repo) {
-            for &dep in dependents {
-                if let Some(deg) = in_degree.get_mut(dep) {
-                    *deg -= 1;
-                    if *deg == 0 {
-                        topo_queue.push_back(dep);
+    while !current.is_empty() {
+        current.sort_unstable();
+        let mut next: Vec<&str> = Vec::new();
+        for &repo in &current {
+            sorted.push(repo);
+            level_of.insert(repo, level);
+            if let Some(dependents) = forward.get(repo) {
+                for &dep in dependents {
+                    if let Some(deg) = in_degree.get_mut(dep) {
+                        *deg -= 1;
+                        if *deg == 0 {
+                            next.push(dep);
+                        }
                     }
                 }
             }
         }
+        current = next;
+        level += 1;
     }
 
     if sorted.len() != affected.len() {
@@ -120,6 +218,7 @@ pub fn compute_update_chain(
             steps.push(UpdateStep {
                 repo: repo.to_string(),
                 inputs,
+                level: level_of[repo],
             });
             updated_so_far.insert(repo);
         }
@@ -128,119 +227,887 @@ pub fn compute_update_chain(
     Ok(steps)
 }
 
+/// Narrow each step's inputs to those in `only` (`flake-update --input`),
+/// dropping steps left with nothing to update. An empty `only` keeps the chain.
+pub fn filter_chain_inputs(chain: Vec<UpdateStep>, only: &[String]) -> Vec<UpdateStep> {
+    if only.is_empty() {
+        return chain;
+    }
+    chain
+        .into_iter()
+        .filter_map(|mut step| {
+            step.inputs.retain(|input| only.contains(input));
+            (!step.inputs.is_empty()).then_some(step)
+        })
+        .collect()
+}
+
 /// Execute the update chain: for each step, run nix flake update, commit, push.
+///
+/// Levels run in order with a barrier between them. Within a level, steps run
+/// concurrently (bounded by `PARALLEL_STEPS`) unless `sequential` is set.
+/// Every pushed commit is journaled as it lands (even when a later step fails)
+/// so the chain can be undone with `tend flake-update --rollback`.
+///
+/// Re-running a chain from scratch is safe: a step whose flake.lock comes out
+/// unchanged commits and pushes nothing. Finished steps are also recorded as
+/// chain progress, so with `resume` an interrupted chain skips them instead.
 pub fn execute_update_chain(
     workspace: &Workspace,
+    repos: &ResolvedRepos,
+    changed: &str,
     chain: &[UpdateStep],
-    dry_run: bool,
-    quiet: bool,
+    opts: &ExecuteOpts,
 ) -> Result<()> {
-    let base_dir = workspace.resolved_base_dir()?;
-    let total = chain.len();
+    let mut record = ChainRecord::start(&workspace.name, changed, opts.resume, !opts.dry_run)?;
+    run_chain(workspace, repos, chain, opts, &mut record)?;
+    record.finish()
+}
+
+/// Progress and journal of the chain being run, written after every step.
+struct ChainRecord<'a> {
+    workspace: &'a str,
+    /// Write to disk (off for dry runs)
+    persist: bool,
+    progress: ChainProgress,
+    journal: FlakeJournal,
+}
 
-    for (i, step) in chain.iter().enumerate() {
-        let step_num = i + 1;
-        let repo_path = base_dir.join(&step.repo);
+impl<'a> ChainRecord<'a> {
+    fn start(workspace: &'a str, changed: &str, resume: bool, persist: bool) -> Result<Self> {
+        let interrupted = if resume {
+            flake_journal::load_progress(workspace)?.filter(|p| p.changed == changed)
+        } else {
+            None
+        };
+        let record = match interrupted {
+            Some(progress) => {
+                // Keep the interrupted run's pushes so a rollback covers the whole chain
+                let journal = flake_journal::load(workspace)?
+                    .filter(|j| j.timestamp == progress.started)
+                    .unwrap_or_else(|| FlakeJournal {
+                        changed: changed.to_string(),
+                        timestamp: progress.started.clone(),
+                        entries: Vec::new(),
+                    });
+                Self {
+                    workspace,
+                    persist,
+                    progress,
+                    journal,
+                }
+            }
+            None => {
+                let started = chrono::Utc::now().to_rfc3339();
+                let record = Self {
+                    workspace,
+                    persist,
+                    progress: ChainProgress {
+                        changed: changed.to_string(),
+                        started: started.clone(),
+                        completed: Vec::new(),
+                    },
+                    journal: FlakeJournal {
+                        changed: changed.to_string(),
+                        timestamp: started,
+                        entries: Vec::new(),
+                    },
+                };
+                // Replace any older chain's progress before the first step runs
+                if persist {
+                    flake_journal::save_progress(workspace, &record.progress)?;
+                }
+                record
+            }
+        };
+        Ok(record)
+    }
+
+    fn is_done(&self, repo: &str) -> bool {
+        self.progress.completed.iter().any(|r| r == repo)
+    }
+
+    fn step_done(&mut self, repo: &str, pushed: Option<JournalEntry>) -> Result<()> {
+        if !self.persist {
+            return Ok(());
+        }
+        self.progress.completed.push(repo.to_string());
+        flake_journal::save_progress(self.workspace, &self.progress)?;
+        // A chain that pushes nothing leaves the previous chain's journal in place
+        if let Some(entry) = pushed {
+            self.journal.entries.push(entry);
+            flake_journal::save(self.workspace, &self.journal)?;
+        }
+        Ok(())
+    }
 
-        if !repo_path.exists() {
-            bail!("repo directory does not exist: {}", repo_path.display());
+    fn finish(self) -> Result<()> {
+        if self.persist {
+            flake_journal::clear_progress(self.workspace)?;
         }
+        Ok(())
+    }
+}
 
-        if !quiet {
-            display::print_flake_step_start(step_num, total, &step.repo, &step.inputs);
+fn run_chain(
+    workspace: &Workspace,
+    repos: &ResolvedRepos,
+    chain: &[UpdateStep],
+    opts: &ExecuteOpts,
+    record: &mut ChainRecord<'_>,
+) -> Result<()> {
+    let env = workspace.resolved_env()?;
+    let commit_author = workspace
+        .flake_commit_author
+        .as_deref()
+        .map(parse_commit_author)
+        .transpose()
+        .with_context(|| format!("invalid flake_commit_author for workspace {}", workspace.name))
+        .map_err(|e| Failure::Config.tag(e))?;
+    let nix_args: Vec<String> = workspace.flake_update_args.iter().chain(opts.nix_args).cloned().collect();
+    let opts = &ExecuteOpts {
+        auto_unshallow: workspace.flake_auto_unshallow,
+        commit_granularity: opts.commit_granularity.or(Some(workspace.flake_commit_granularity)),
+        commit_author,
+        nix_args: &nix_args,
+        ..*opts
+    };
+    let total = chain.len();
+    let mut offset = 0usize;
+
+    for level in chain.chunk_by(|a, b| a.level == b.level) {
+        // (step number, step) for the steps this run still has to do
+        let mut pending = Vec::new();
+        for (i, step) in level.iter().enumerate() {
+            if record.is_done(&step.repo) {
+                if !opts.quiet {
+                    display::print_flake_step_resumed(offset + i + 1, total, &step.repo);
+                }
+            } else {
+                pending.push((offset + i + 1, step));
+            }
         }
 
-        if dry_run {
-            if !quiet {
-                display::print_flake_step_dry_run();
+        if opts.sequential || opts.interactive || pending.len() <= 1 {
+            for &(step_num, step) in &pending {
+                let repo_path = repos.path(workspace, &step.repo)?;
+                let pushed = execute_step(&repo_path, &env, step, step_num, total, opts)?;
+                record.step_done(&step.repo, pushed)?;
+            }
+        } else {
+            // A failed step stops new ones from starting; those already running finish
+            let failed = AtomicBool::new(false);
+            let results = sync::run_bounded(
+                &pending,
+                PARALLEL_STEPS,
+                || failed.load(Ordering::Relaxed),
+                |&(step_num, step)| {
+                    let result = repos
+                        .path(workspace, &step.repo)
+                        .and_then(|repo_path| execute_step(&repo_path, &env, step, step_num, total, opts));
+                    if result.is_err() {
+                        failed.store(true, Ordering::Relaxed);
+                    }
+                    result
+                },
+            );
+            // Record what finished, then surface the first failure
+            let mut first_err = None;
+            for (&(_, step), result) in pending.iter().zip(results) {
+                let Some(result) = result else {
+                    continue;
+                };
+                if let Err(e) = result.and_then(|pushed| record.step_done(&step.repo, pushed)) {
+                    first_err.get_or_insert(e);
+                }
+            }
+            if let Some(e) = first_err {
+                return Err(e);
             }
-            continue;
         }
+        offset += level.len();
+    }
 
-        // Check for clean working tree
-        ensure_clean(&repo_path)
-            .with_context(|| format!("{} has uncommitted changes", step.repo))?;
+    Ok(())
+}
+
+/// Run a single step: nix flake update, then commit and push if flake.lock changed.
+/// Returns the pushed commit, or `None` when nothing was pushed.
+fn execute_step(
+    repo_path: &Path,
+    env: &HashMap<String, String>,
+    step: &UpdateStep,
+    step_num: usize,
+    total: usize,
+    opts: &ExecuteOpts,
+) -> Result<Option<JournalEntry>> {
+    let quiet = opts.quiet;
+
+    if !repo_path.exists() {
+        bail!("repo directory does not exist: {}", repo_path.display());
+    }
+
+    if !quiet {
+        display::print_flake_step_start(step_num, total, &step.repo, &step.inputs);
+    }
+
+    if opts.require_default_branch {
+        ensure_default_branch(repo_path, env)
+            .with_context(|| format!("refusing to update {}", step.repo))?;
+    }
+
+    if let Some(op) = sync::in_progress_operation(repo_path, env) {
+        bail!(
+            "refusing to update {}: {} (finish it or run `{}`)",
+            step.repo,
+            op.label(),
+            op.abort_command()
+        );
+    }
+
+    if sync::is_detached_head(repo_path, env) {
+        bail!(
+            "refusing to update {}: detached HEAD (check out a branch first)",
+            step.repo
+        );
+    }
+
+    let shallow = is_shallow(repo_path);
+    if shallow && !opts.auto_unshallow {
+        bail!(
+            "refusing to update {}: shallow clone (run `git fetch --unshallow` or set flake_auto_unshallow: true)",
+            step.repo
+        );
+    }
 
-        // nix flake update <inputs...>
-        let mut args = vec!["flake", "update"];
-        for input in &step.inputs {
-            args.push(input);
+    if opts.dry_run {
+        if !quiet {
+            display::print_flake_step_dry_run();
         }
+        return Ok(None);
+    }
 
-        let output = Command::new("nix")
-            .args(&args)
-            .current_dir(&repo_path)
-            .output()
-            .with_context(|| format!("running nix flake update in {}", step.repo))?;
+    if shallow {
+        let output = runner::output(
+            Command::new("git")
+                .args(["fetch", "--unshallow", "--quiet"])
+                .envs(env)
+                .current_dir(repo_path),
+        )
+        .with_context(|| format!("git fetch --unshallow in {}", step.repo))?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            bail!("nix flake update failed in {}: {}", step.repo, stderr);
+            return Err(git_err!("git fetch --unshallow failed in {}: {}", step.repo, stderr));
         }
+    }
 
-        // git add flake.lock
-        let output = Command::new("git")
-            .args(["add", "flake.lock"])
-            .current_dir(&repo_path)
-            .output()
-            .with_context(|| format!("git add flake.lock in {}", step.repo))?;
+    // Stashed work isn't in `git status`, so mention it before committing around it
+    let stashes = sync::stash_count(repo_path, env);
+    if stashes > 0 {
+        display::print_flake_step_stash_warning(&step.repo, stashes);
+    }
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            bail!("git add failed in {}: {}", step.repo, stderr);
+    // Check for clean working tree
+    ensure_clean(repo_path, env)
+        .with_context(|| format!("{} has uncommitted changes", step.repo))?;
+
+    // Each group of inputs becomes one commit; the push covers them all
+    let before = rev_parse_head(repo_path, env)?;
+    let mut committed = 0usize;
+    let mut declined = false;
+    for inputs in commit_groups(&step.inputs, opts.commit_granularity.unwrap_or_default()) {
+        match update_and_commit(repo_path, env, &step.repo, &inputs, opts)? {
+            LockUpdate::Committed => committed += 1,
+            LockUpdate::Unchanged => {}
+            LockUpdate::Declined => declined = true,
+        }
+    }
+
+    if committed == 0 {
+        // No changes staged — lock file unchanged (or every change declined)
+        if !quiet && !declined {
+            display::print_flake_step_no_changes(&step.repo);
         }
+        return Ok(None);
+    }
+
+    // Push
+    let output = runner::output(
+        Command::new("git")
+            .args(["push"])
+            .envs(env)
+            .current_dir(repo_path),
+    )
+    .with_context(|| format!("git push in {}", step.repo))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(git_err!("git push failed in {}: {}", step.repo, stderr));
+    }
 
-        // Check if flake.lock actually changed
-        let diff = Command::new("git")
-            .args(["diff", "--cached", "--quiet"])
-            .current_dir(&repo_path)
-            .status()
-            .with_context(|| format!("checking staged changes in {}", step.repo))?;
+    if !quiet {
+        display::print_flake_step_done(&step.repo);
+    }
 
-        if diff.success() {
-            // No changes staged — lock file unchanged
-            if !quiet {
-                display::print_flake_step_no_changes(&step.repo);
+    Ok(Some(JournalEntry {
+        repo: step.repo.clone(),
+        before,
+        after: rev_parse_head(repo_path, env)?,
+    }))
+}
+
+/// What happened to one `nix flake update` of a repo.
+enum LockUpdate {
+    Committed,
+    Unchanged,
+    /// Changed, but rejected at the `--interactive` prompt
+    Declined,
+}
+
+/// Inputs to update together, one group per commit.
+fn commit_groups(inputs: &[String], granularity: FlakeCommitGranularity) -> Vec<Vec<String>> {
+    match granularity {
+        FlakeCommitGranularity::PerRepo => vec![inputs.to_vec()],
+        FlakeCommitGranularity::PerInput => inputs.iter().map(|i| vec![i.clone()]).collect(),
+    }
+}
+
+/// Split a `Name <email>` author into its name and email.
+fn parse_commit_author(author: &str) -> Result<(&str, &str)> {
+    let parsed = author
+        .trim()
+        .strip_suffix('>')
+        .and_then(|rest| rest.split_once('<'))
+        .map(|(name, email)| (name.trim(), email.trim()))
+        .filter(|(name, email)| !name.is_empty() && !email.is_empty());
+    parsed.with_context(|| format!("expected `Name <email>`, got '{author}'"))
+}
+
+/// `git commit` arguments, with `-c user.name=... -c user.email=...` first
+/// when an author is set so the repo's own git user is overridden.
+fn commit_args(msg: &str, author: Option<(&str, &str)>) -> Vec<String> {
+    let mut args = Vec::new();
+    if let Some((name, email)) = author {
+        args.extend(["-c".to_string(), format!("user.name={name}")]);
+        args.extend(["-c".to_string(), format!("user.email={email}")]);
+    }
+    args.extend(["commit".to_string(), "-m".to_string(), msg.to_string()]);
+    args
+}
+
+/// `nix flake update <inputs...> <nix_args...>`; the extra arguments go
+/// through verbatim.
+fn flake_update_args<'a>(inputs: &'a [String], nix_args: &'a [String]) -> Vec<&'a str> {
+    ["flake", "update"]
+        .into_iter()
+        .chain(inputs.iter().chain(nix_args).map(String::as_str))
+        .collect()
+}
+
+/// Run `nix flake update <inputs...>` and commit flake.lock if it changed.
+fn update_and_commit(
+    repo_path: &Path,
+    env: &HashMap<String, String>,
+    repo: &str,
+    inputs: &[String],
+    opts: &ExecuteOpts,
+) -> Result<LockUpdate> {
+    let output = runner::output(
+        Command::new("nix")
+            .args(flake_update_args(inputs, opts.nix_args))
+            .envs(env)
+            .current_dir(repo_path),
+    )
+    .with_context(|| format!("running nix flake update in {repo}"))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("nix flake update failed in {}: {}", repo, stderr);
+    }
+
+    // git add flake.lock
+    let output = runner::output(
+        Command::new("git")
+            .args(["add", "flake.lock"])
+            .envs(env)
+            .current_dir(repo_path),
+    )
+    .with_context(|| format!("git add flake.lock in {repo}"))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(git_err!("git add failed in {}: {}", repo, stderr));
+    }
+
+    // Check if flake.lock actually changed
+    let diff = Command::new("git")
+        .args(["diff", "--cached", "--quiet"])
+        .envs(env)
+        .current_dir(repo_path)
+        .status()
+        .with_context(|| format!("checking staged changes in {repo}"))?;
+
+    if diff.success() {
+        return Ok(LockUpdate::Unchanged);
+    }
+
+    if opts.interactive && !review_lock_update(repo_path, env, repo)? {
+        return Ok(LockUpdate::Declined);
+    }
+
+    // Commit
+    let msg = format!("chore: update {}", inputs.join(" "));
+    let output = runner::output(
+        Command::new("git")
+            .args(commit_args(&msg, opts.commit_author))
+            .envs(env)
+            .current_dir(repo_path),
+    )
+    .with_context(|| format!("git commit in {repo}"))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(git_err!("git commit failed in {}: {}", repo, stderr));
+    }
+    Ok(LockUpdate::Committed)
+}
+
+/// Show the staged flake.lock changes and ask whether to commit them.
+/// On a no, the lock file is restored to HEAD and `false` is returned.
+fn review_lock_update(repo_path: &Path, env: &HashMap<String, String>, repo: &str) -> Result<bool> {
+    let old = Command::new("git")
+        .args(["show", "HEAD:flake.lock"])
+        .envs(env)
+        .current_dir(repo_path)
+        .output()
+        .with_context(|| format!("reading committed flake.lock in {repo}"))?;
+    // A first lock file has nothing committed to compare against
+    let old_content = if old.status.success() {
+        String::from_utf8_lossy(&old.stdout).to_string()
+    } else {
+        String::new()
+    };
+    let new_content = std::fs::read_to_string(repo_path.join("flake.lock"))
+        .with_context(|| format!("reading flake.lock in {repo}"))?;
+
+    let changes = lock_changes(&old_content, &new_content)
+        .with_context(|| format!("comparing flake.lock in {repo}"))?;
+    display::print_flake_lock_changes(repo, &changes);
+    display::print_flake_confirm_prompt(repo);
+
+    let mut answer = String::new();
+    std::io::stdin()
+        .lock()
+        .read_line(&mut answer)
+        .context("reading answer from stdin")?;
+    if matches!(answer.trim(), "y" | "Y" | "yes") {
+        return Ok(true);
+    }
+
+    let restore: &[&str] = if old.status.success() {
+        &["checkout", "HEAD", "--", "flake.lock"]
+    } else {
+        &["rm", "-q", "-f", "flake.lock"]
+    };
+    let output = runner::output(
+        Command::new("git")
+            .args(restore)
+            .envs(env)
+            .current_dir(repo_path),
+    )
+    .with_context(|| format!("restoring flake.lock in {repo}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("restoring flake.lock failed in {}: {}", repo, stderr);
+    }
+    display::print_flake_step_declined(repo);
+    Ok(false)
+}
+
+/// Inputs whose locked revision differs between two flake.lock contents,
+/// sorted by input name. An empty `old` treats every input as new.
+pub fn lock_changes(old: &str, new: &str) -> Result<Vec<LockChange>> {
+    let old = locked_revs(old)?;
+    let new = locked_revs(new)?;
+    let inputs: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    Ok(inputs
+        .into_iter()
+        .filter(|input| old.get(*input) != new.get(*input))
+        .map(|input| LockChange {
+            input: input.clone(),
+            old: old.get(input).cloned(),
+            new: new.get(input).cloned(),
+        })
+        .collect())
+}
+
+/// Map each locked node in a flake.lock to its `rev` (or `narHash` for inputs without one).
+fn locked_revs(content: &str) -> Result<BTreeMap<String, String>> {
+    if content.trim().is_empty() {
+        return Ok(BTreeMap::new());
+    }
+    let lock: serde_json::Value = serde_json::from_str(content).context("parsing flake.lock")?;
+    let mut revs = BTreeMap::new();
+    if let Some(nodes) = lock.get("nodes").and_then(|n| n.as_object()) {
+        for (name, node) in nodes {
+            let Some(locked) = node.get("locked") else {
+                continue;
+            };
+            if let Some(rev) = locked
+                .get("rev")
+                .or_else(|| locked.get("narHash"))
+                .and_then(|r| r.as_str())
+            {
+                revs.insert(name.clone(), rev.to_string());
             }
+        }
+    }
+    Ok(revs)
+}
+
+/// Check every `flake_deps` input of each repo in the chain against the root
+/// inputs of the repo's flake.lock
+/// (the inputs `nix flake metadata` reports), so a stale `flake_deps` entry is
+/// reported before the chain runs rather than as a nix error halfway through.
+/// Repos without a flake.lock (e.g. not cloned yet) can't be checked and are skipped.
+pub fn check_chain_inputs(workspace: &Workspace, repos: &ResolvedRepos, chain: &[UpdateStep]) -> Result<Vec<MissingInput>> {
+    let mut missing = Vec::new();
+    for step in chain {
+        let lock_path = repos.path(workspace, &step.repo)?.join("flake.lock");
+        let Ok(content) = std::fs::read_to_string(&lock_path) else {
+            continue;
+        };
+        let declared = root_inputs(&content).with_context(|| format!("reading {}", lock_path.display()))?;
+        let Some(inputs) = workspace.flake_deps.get(&step.repo) else {
             continue;
+        };
+        missing.extend(
+            inputs
+                .iter()
+                .filter(|input| !declared.contains(*input))
+                .map(|input| MissingInput {
+                    repo: step.repo.clone(),
+                    input: input.clone(),
+                }),
+        );
+    }
+    Ok(missing)
+}
+
+/// Names of the root flake's inputs in a flake.lock.
+fn root_inputs(content: &str) -> Result<BTreeSet<String>> {
+    let lock: serde_json::Value = serde_json::from_str(content).context("parsing flake.lock")?;
+    let root = lock.get("root").and_then(|r| r.as_str()).unwrap_or("root");
+    Ok(lock
+        .pointer(&format!("/nodes/{root}/inputs"))
+        .and_then(|i| i.as_object())
+        .map(|inputs| inputs.keys().cloned().collect())
+        .unwrap_or_default())
+}
+
+/// Compare each `flake_deps` input's locked rev against its upstream's
+/// current rev, resolved with `nix flake metadata --refresh` on the input's
+/// original flake ref. Repos come in dependency order, upstreams first.
+/// Nothing is changed: this is what `flake-update` would have to do.
+pub fn flake_status(workspace: &Workspace, repos: &ResolvedRepos) -> Result<Vec<RepoFlakeStatus>> {
+    let env = workspace.resolved_env()?;
+    flake_status_with(workspace, repos, |flake_ref| upstream_rev(flake_ref, &env))
+}
+
+/// `flake_status` with the upstream lookup injected; each flake ref is resolved once.
+fn flake_status_with(
+    workspace: &Workspace,
+    repos: &ResolvedRepos,
+    mut resolve: impl FnMut(&str) -> Result<String>,
+) -> Result<Vec<RepoFlakeStatus>> {
+    let mut upstream: HashMap<String, Result<String, String>> = HashMap::new();
+    let mut statuses = Vec::new();
+
+    for repo in dependency_order(&workspace.flake_deps) {
+        let mut status = RepoFlakeStatus { repo: repo.clone(), ..Default::default() };
+        let lock_path = repos.path(workspace, &repo)?.join("flake.lock");
+        let Ok(content) = std::fs::read_to_string(&lock_path) else {
+            status.no_lock = true;
+            statuses.push(status);
+            continue;
+        };
+        let lock: serde_json::Value = serde_json::from_str(&content)
+            .with_context(|| format!("parsing {}", lock_path.display()))?;
+
+        for input in &workspace.flake_deps[&repo] {
+            let (locked, flake_ref) = match locked_input(&lock, input) {
+                Ok(found) => found,
+                Err(reason) => {
+                    status.unchecked.push((input.clone(), reason));
+                    continue;
+                }
+            };
+            let current = upstream
+                .entry(flake_ref.clone())
+                .or_insert_with(|| resolve(&flake_ref).map_err(|e| format!("{e:#}")));
+            match current {
+                Ok(rev) if *rev != locked => status.stale.push(StaleInput {
+                    input: input.clone(),
+                    locked,
+                    upstream: rev.clone(),
+                }),
+                Ok(_) => {}
+                Err(reason) => status.unchecked.push((input.clone(), reason.clone())),
+            }
         }
+        statuses.push(status);
+    }
+    Ok(statuses)
+}
 
-        // Commit
-        let msg = format!("chore: update {}", step.inputs.join(" "));
-        let output = Command::new("git")
-            .args(["commit", "-m", &msg])
-            .current_dir(&repo_path)
-            .output()
-            .with_context(|| format!("git commit in {}", step.repo))?;
+/// `flake_deps` repos ordered so each comes after the repos it takes as
+/// inputs (Kahn's algorithm, ties by name). Repos caught in a cycle go last.
+fn dependency_order(flake_deps: &HashMap<String, Vec<String>>) -> Vec<String> {
+    let mut pending: BTreeMap<&str, BTreeSet<&str>> = flake_deps
+        .iter()
+        .map(|(repo, deps)| {
+            let upstreams = deps
+                .iter()
+                .map(String::as_str)
+                .filter(|dep| dep != repo && flake_deps.contains_key(*dep))
+                .collect();
+            (repo.as_str(), upstreams)
+        })
+        .collect();
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            bail!("git commit failed in {}: {}", step.repo, stderr);
+    let mut order = Vec::new();
+    loop {
+        let ready: Vec<&str> = pending
+            .iter()
+            .filter(|(_, upstreams)| upstreams.is_empty())
+            .map(|(repo, _)| *repo)
+            .collect();
+        if ready.is_empty() {
+            break;
+        }
+        for repo in ready {
+            pending.remove(repo);
+            pending.values_mut().for_each(|upstreams| {
+                upstreams.remove(repo);
+            });
+            order.push(repo.to_string());
         }
+    }
+    order.extend(pending.into_keys().map(str::to_string));
+    order
+}
 
-        // Push
-        let output = Command::new("git")
-            .args(["push"])
-            .current_dir(&repo_path)
-            .output()
-            .with_context(|| format!("git push in {}", step.repo))?;
+/// The locked rev of root input `input` and the flake ref its upstream is
+/// tracked by, or why it can't be checked.
+fn locked_input(lock: &serde_json::Value, input: &str) -> Result<(String, String), String> {
+    let root = lock.get("root").and_then(|r| r.as_str()).unwrap_or("root");
+    let node_name = match lock.pointer(&format!("/nodes/{root}/inputs/{input}")) {
+        Some(serde_json::Value::String(node)) => node.clone(),
+        Some(_) => return Err("follows another input".to_string()),
+        None => return Err("not an input of this flake".to_string()),
+    };
+    let node = &lock["nodes"][&node_name];
+    let locked = node
+        .pointer("/locked/rev")
+        .and_then(|r| r.as_str())
+        .ok_or_else(|| "locked without a rev".to_string())?;
+    let flake_ref = original_flake_ref(&node["original"])
+        .ok_or_else(|| "unsupported input type".to_string())?;
+    Ok((locked.to_string(), flake_ref))
+}
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            bail!("git push failed in {}: {}", step.repo, stderr);
+/// Flake ref for a flake.lock `original` node (github, gitlab, sourcehut, git).
+fn original_flake_ref(original: &serde_json::Value) -> Option<String> {
+    let field = |key: &str| original.get(key).and_then(|v| v.as_str());
+    match field("type")? {
+        kind @ ("github" | "gitlab" | "sourcehut") => {
+            let mut flake_ref = format!("{kind}:{}/{}", field("owner")?, field("repo")?);
+            if let Some(git_ref) = field("ref") {
+                flake_ref.push('/');
+                flake_ref.push_str(git_ref);
+            }
+            Some(flake_ref)
+        }
+        "git" => {
+            let url = field("url")?;
+            let url = if url.starts_with("git+") { url.to_string() } else { format!("git+{url}") };
+            Some(match field("ref") {
+                Some(git_ref) => format!("{url}?ref={git_ref}"),
+                None => url,
+            })
         }
+        _ => None,
+    }
+}
 
-        if !quiet {
-            display::print_flake_step_done(&step.repo);
+/// Current rev of `flake_ref` upstream, bypassing nix's fetcher cache.
+fn upstream_rev(flake_ref: &str, env: &HashMap<String, String>) -> Result<String> {
+    let output = Command::new("nix")
+        .args(["flake", "metadata", "--json", "--refresh", flake_ref])
+        .envs(env)
+        .output()
+        .with_context(|| format!("running nix flake metadata {flake_ref}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("nix flake metadata {flake_ref} failed: {}", stderr.trim());
+    }
+    let metadata: serde_json::Value =
+        serde_json::from_slice(&output.stdout).context("parsing nix flake metadata output")?;
+    metadata
+        .get("revision")
+        .or_else(|| metadata.pointer("/locked/rev"))
+        .and_then(|r| r.as_str())
+        .map(str::to_string)
+        .with_context(|| format!("nix flake metadata {flake_ref} reported no revision"))
+}
+
+/// Undo a journaled chain, newest commit first: reset each repo to its
+/// pre-update commit and force-push it.
+///
+/// Without `force` nothing is changed; the exact commands are printed instead.
+/// A repo whose HEAD is not the journaled commit, or whose working tree is
+/// dirty, is refused, since resetting it would discard work that isn't part
+/// of the chain.
+pub fn rollback_chain(workspace: &Workspace, repos: &ResolvedRepos, journal: &FlakeJournal, force: bool) -> Result<()> {
+    let env = workspace.resolved_env()?;
+
+    for entry in journal.entries.iter().rev() {
+        let repo_path = repos.path(workspace, &entry.repo)?;
+        let branch = current_branch(&repo_path, &env)
+            .with_context(|| format!("refusing to roll back {}", entry.repo))?;
+        let lease = format!("--force-with-lease={branch}:{}", entry.after);
+        let reset = ["reset", "--hard", entry.before.as_str()];
+        let push = ["push", lease.as_str(), workspace.remote_name(), branch.as_str()];
+
+        // Checked before printing too, so a preview fails where the real run would
+        let head = rev_parse_head(&repo_path, &env)?;
+        if head != entry.after {
+            bail!(
+                "refusing to roll back {}: HEAD is {head}, expected {} (roll back by hand)",
+                entry.repo,
+                entry.after
+            );
         }
+        ensure_clean(&repo_path, &env)
+            .with_context(|| format!("refusing to roll back {} (reset --hard would discard it)", entry.repo))?;
+
+        if !force {
+            display::print_flake_rollback_commands(&entry.repo, &repo_path, &[&reset[..], &push[..]]);
+            continue;
+        }
+
+        for args in [&reset[..], &push[..]] {
+            let output = runner::output(
+                Command::new("git")
+                    .args(args)
+                    .envs(&env)
+                    .current_dir(&repo_path),
+            )
+            .with_context(|| format!("git {} in {}", args[0], entry.repo))?;
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                return Err(git_err!("git {} failed in {}: {}", args[0], entry.repo, stderr));
+            }
+        }
+        display::print_flake_rollback_done(&entry.repo, &entry.before);
     }
 
     Ok(())
 }
 
-fn ensure_clean(repo_path: &Path) -> Result<()> {
+fn rev_parse_head(repo_path: &Path, env: &HashMap<String, String>) -> Result<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .envs(env)
+        .current_dir(repo_path)
+        .output()
+        .with_context(|| format!("reading HEAD in {}", repo_path.display()))?;
+    if !output.status.success() {
+        return Err(git_err!("git rev-parse HEAD failed in {}", repo_path.display()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn current_branch(repo_path: &Path, env: &HashMap<String, String>) -> Result<String> {
+    let output = Command::new("git")
+        .args(["symbolic-ref", "--short", "-q", "HEAD"])
+        .envs(env)
+        .current_dir(repo_path)
+        .output()
+        .with_context(|| format!("reading current branch in {}", repo_path.display()))?;
+    if !output.status.success() {
+        bail!("no branch checked out in {}", repo_path.display());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Detect the remote's default branch from `refs/remotes/<remote>/HEAD`.
+fn remote_default_branch(repo_path: &Path, env: &HashMap<String, String>, remote: &str) -> Result<String> {
+    let head_ref = format!("refs/remotes/{remote}/HEAD");
+    let output = Command::new("git")
+        .args(["symbolic-ref", &head_ref])
+        .envs(env)
+        .current_dir(repo_path)
+        .output()
+        .with_context(|| format!("reading {remote}/HEAD in {}", repo_path.display()))?;
+
+    if !output.status.success() {
+        bail!("{remote}/HEAD is not set (run `git remote set-head {remote} --auto`)");
+    }
+    parse_remote_head(&String::from_utf8_lossy(&output.stdout), remote)
+        .ok_or_else(|| anyhow::anyhow!("unexpected {remote}/HEAD ref"))
+}
+
+/// Extract the branch name from `refs/remotes/<remote>/<branch>`.
+fn parse_remote_head(symbolic_ref: &str, remote: &str) -> Option<String> {
+    symbolic_ref
+        .trim()
+        .strip_prefix("refs/remotes/")
+        .and_then(|r| r.strip_prefix(remote))
+        .and_then(|r| r.strip_prefix('/'))
+        .filter(|b| !b.is_empty())
+        .map(str::to_string)
+}
+
+/// Fail unless the checked-out branch is the remote's default branch.
+/// The remote is the one the branch tracks, so `origin_name` clones work too.
+fn ensure_default_branch(repo_path: &Path, env: &HashMap<String, String>) -> Result<()> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--abbrev-ref", "HEAD"])
+        .envs(env)
+        .current_dir(repo_path)
+        .output()
+        .with_context(|| format!("reading current branch in {}", repo_path.display()))?;
+    let current = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    let remote = Command::new("git")
+        .args(["config", &format!("branch.{current}.remote")])
+        .envs(env)
+        .current_dir(repo_path)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .filter(|r| !r.is_empty())
+        .unwrap_or_else(|| "origin".to_string());
+    let default = remote_default_branch(repo_path, env, &remote)?;
+
+    if current != default {
+        bail!("checked out on '{current}', expected default branch '{default}'");
+    }
+    Ok(())
+}
+
+/// A shallow clone records its grafted commits in `.git/shallow`.
+fn is_shallow(repo_path: &Path) -> bool {
+    repo_path.join(".git").join("shallow").exists()
+}
+
+fn ensure_clean(repo_path: &Path, env: &HashMap<String, String>) -> Result<()> {
     let output = Command::new("git")
         .args(["status", "--porcelain"])
+        .envs(env)
         .current_dir(repo_path)
         .output()
         .with_context(|| format!("checking git status in {}", repo_path.display()))?;
@@ -250,3 +1117,452 @@ fn ensure_clean(repo_path: &Path) -> Result<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_workspace() -> Workspace {
+        Workspace {
+            name: "test-flake".to_string(),
+            base_dir: "/tmp/tend-test-flake".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn deps(pairs: &[(&str, &[&str])]) -> HashMap<String, Vec<String>> {
+        pairs
+            .iter()
+            .map(|(repo, inputs)| {
+                (repo.to_string(), inputs.iter().map(|i| i.to_string()).collect())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_compute_update_chain_levels() {
+        // lib → {app-a, app-b} → nix
+        let flake_deps = deps(&[
+            ("app-a", &["lib"]),
+            ("app-b", &["lib"]),
+            ("nix", &["app-a", "app-b", "nixpkgs"]),
+        ]);
+        let chain = compute_update_chain("lib", &flake_deps).unwrap();
+
+        let summary: Vec<(&str, usize)> = chain.iter().map(|s| (s.repo.as_str(), s.level)).collect();
+        assert_eq!(summary, vec![("app-a", 0), ("app-b", 0), ("nix", 1)]);
+        assert_eq!(chain[2].inputs, vec!["app-a", "app-b"]);
+    }
+
+    #[test]
+    fn test_filter_chain_inputs_narrows_and_drops_steps() {
+        let flake_deps = deps(&[("app", &["lib", "util"]), ("util", &["lib"]), ("site", &["app"])]);
+        let chain = compute_update_chain("lib", &flake_deps).unwrap();
+        assert_eq!(filter_chain_inputs(chain.clone(), &[]), chain);
+
+        let chain = filter_chain_inputs(chain, &["lib".to_string()]);
+        let summary: Vec<(&str, &[String])> = chain.iter().map(|s| (s.repo.as_str(), s.inputs.as_slice())).collect();
+        assert_eq!(summary, vec![("util", &["lib".to_string()][..]), ("app", &["lib".to_string()][..])]);
+    }
+
+    #[test]
+    fn test_update_plan_serializes_steps_in_order() {
+        let flake_deps = deps(&[("app", &["lib"]), ("nix", &["app"])]);
+        let chain = compute_update_chain("lib", &flake_deps).unwrap();
+        let plan = UpdatePlan { workspace: "ws", changed: "lib", steps: &chain };
+        let json: serde_json::Value = serde_json::to_value(&plan).unwrap();
+        assert_eq!(json["changed"], "lib");
+        assert_eq!(
+            json["steps"],
+            serde_json::json!([
+                {"repo": "app", "inputs": ["lib"], "level": 0},
+                {"repo": "nix", "inputs": ["app"], "level": 1},
+            ])
+        );
+    }
+
+    #[test]
+    fn test_shallow_clone_refused_without_auto_unshallow() {
+        let repo = std::env::temp_dir().join(format!("tend-test-shallow-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&repo);
+        std::fs::create_dir_all(repo.join(".git")).unwrap();
+        std::fs::write(repo.join(".git").join("shallow"), "abc123\n").unwrap();
+
+        let step = UpdateStep {
+            repo: "lib".to_string(),
+            inputs: vec!["base".to_string()],
+            level: 0,
+        };
+        let opts = ExecuteOpts {
+            dry_run: true,
+            quiet: true,
+            ..Default::default()
+        };
+        let err = execute_step(&repo, &HashMap::new(), &step, 1, 1, &opts).unwrap_err();
+        assert!(err.to_string().contains("shallow clone"));
+
+        let opts = ExecuteOpts {
+            auto_unshallow: true,
+            ..opts
+        };
+        assert!(execute_step(&repo, &HashMap::new(), &step, 1, 1, &opts).is_ok());
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn test_detached_head_refused() {
+        let repo = std::env::temp_dir().join(format!("tend-test-flake-detached-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&repo);
+        std::fs::create_dir_all(&repo).unwrap();
+        for args in [
+            &["init", "-q"][..],
+            &["-c", "user.name=t", "-c", "user.email=t@t", "commit", "-q", "--allow-empty", "-m", "init"],
+            &["checkout", "-q", "--detach"],
+        ] {
+            Command::new("git").args(args).current_dir(&repo).status().unwrap();
+        }
+
+        let step = UpdateStep {
+            repo: "lib".to_string(),
+            inputs: vec!["base".to_string()],
+            level: 0,
+        };
+        let opts = ExecuteOpts {
+            dry_run: true,
+            quiet: true,
+            ..Default::default()
+        };
+        let err = execute_step(&repo, &HashMap::new(), &step, 1, 1, &opts).unwrap_err();
+        assert!(err.to_string().contains("detached HEAD"));
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn test_rollback_chain_resets_and_force_pushes() {
+        let base = std::env::temp_dir().join(format!("tend-test-rollback-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&base);
+        std::fs::create_dir_all(&base).unwrap();
+        let git = |dir: &Path, args: &[&str]| {
+            let out = Command::new("git")
+                .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+                .args(args)
+                .current_dir(dir)
+                .output()
+                .unwrap();
+            assert!(out.status.success(), "git {args:?}: {}", String::from_utf8_lossy(&out.stderr));
+            String::from_utf8_lossy(&out.stdout).trim().to_string()
+        };
+        git(&base, &["init", "-q", "--bare", "-b", "main", "remote.git"]);
+        git(&base, &["clone", "-q", "remote.git", "lib"]);
+        let repo = base.join("lib");
+        git(&repo, &["checkout", "-q", "-b", "main"]);
+        git(&repo, &["commit", "-q", "--allow-empty", "-m", "init"]);
+        git(&repo, &["push", "-q", "origin", "main"]);
+        let before = git(&repo, &["rev-parse", "HEAD"]);
+        git(&repo, &["commit", "-q", "--allow-empty", "-m", "chore: update base"]);
+        git(&repo, &["push", "-q", "origin", "main"]);
+        let after = git(&repo, &["rev-parse", "HEAD"]);
+
+        let mut ws = make_workspace();
+        ws.base_dir = base.to_string_lossy().to_string();
+        let journal = FlakeJournal {
+            changed: "base".to_string(),
+            timestamp: String::new(),
+            entries: vec![JournalEntry {
+                repo: "lib".to_string(),
+                before: before.clone(),
+                after: after.clone(),
+            }],
+        };
+
+        // Without force nothing moves
+        rollback_chain(&ws, &ResolvedRepos::default(), &journal, false).unwrap();
+        assert_eq!(git(&repo, &["rev-parse", "HEAD"]), after);
+
+        // A dirty working tree is refused rather than discarded by reset --hard
+        std::fs::write(repo.join("scratch"), "wip").unwrap();
+        let err = rollback_chain(&ws, &ResolvedRepos::default(), &journal, true).unwrap_err();
+        assert!(format!("{err:#}").contains("working tree is dirty"));
+        assert_eq!(git(&repo, &["rev-parse", "HEAD"]), after);
+        std::fs::remove_file(repo.join("scratch")).unwrap();
+
+        rollback_chain(&ws, &ResolvedRepos::default(), &journal, true).unwrap();
+        assert_eq!(git(&repo, &["rev-parse", "HEAD"]), before);
+        assert_eq!(git(&base.join("remote.git"), &["rev-parse", "main"]), before);
+
+        // HEAD no longer matches the journal, so a second rollback is refused
+        let err = rollback_chain(&ws, &ResolvedRepos::default(), &journal, true).unwrap_err();
+        assert!(err.to_string().contains("roll back by hand"));
+        let err = rollback_chain(&ws, &ResolvedRepos::default(), &journal, false).unwrap_err();
+        assert!(err.to_string().contains("roll back by hand"));
+
+        let _ = std::fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn test_resumed_chain_skips_completed_steps() {
+        // No repo directories exist, so any step that actually runs fails
+        let mut ws = make_workspace();
+        ws.base_dir = std::env::temp_dir()
+            .join(format!("tend-test-resume-{}", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        let chain = compute_update_chain("lib", &deps(&[("app", &["lib"]), ("nix", &["app"])])).unwrap();
+        let opts = ExecuteOpts {
+            quiet: true,
+            ..Default::default()
+        };
+        let mut record = ChainRecord {
+            workspace: "test-flake",
+            persist: false,
+            progress: ChainProgress {
+                changed: "lib".to_string(),
+                started: String::new(),
+                completed: vec!["app".to_string()],
+            },
+            journal: FlakeJournal::default(),
+        };
+
+        let err = run_chain(&ws, &ResolvedRepos::default(), &chain, &opts, &mut record).unwrap_err();
+        assert!(err.to_string().contains("tend-test-resume"));
+        assert!(err.to_string().ends_with("nix"));
+
+        record.progress.completed.push("nix".to_string());
+        run_chain(&ws, &ResolvedRepos::default(), &chain, &opts, &mut record).unwrap();
+    }
+
+    #[test]
+    fn test_rerun_with_unchanged_lock_is_a_no_op() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let base = std::env::temp_dir().join(format!("tend-test-flake-rerun-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&base);
+        // A `nix` that leaves flake.lock as it is, as when no input moved
+        let bin = base.join("bin");
+        std::fs::create_dir_all(&bin).unwrap();
+        std::fs::write(bin.join("nix"), "#!/bin/sh\nexit 0\n").unwrap();
+        std::fs::set_permissions(bin.join("nix"), std::fs::Permissions::from_mode(0o755)).unwrap();
+        let app = base.join("app");
+        std::fs::create_dir_all(&app).unwrap();
+        std::fs::write(app.join("flake.lock"), "{}\n").unwrap();
+        for args in [&["init", "-q", "-b", "main"][..], &["add", "."], &["commit", "-q", "-m", "init"]] {
+            Command::new("git")
+                .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+                .args(args)
+                .current_dir(&app)
+                .status()
+                .unwrap();
+        }
+        let head = || rev_parse_head(&app, &HashMap::new()).unwrap();
+        let before = head();
+
+        let mut ws = make_workspace();
+        ws.base_dir = base.to_string_lossy().to_string();
+        let path = std::env::var("PATH").unwrap_or_default();
+        ws.env.insert("PATH".to_string(), format!("{}:{path}", bin.display()));
+        let chain = compute_update_chain("lib", &deps(&[("app", &["lib"])])).unwrap();
+        let opts = ExecuteOpts {
+            quiet: true,
+            ..Default::default()
+        };
+        // Running the same chain again (e.g. after an interruption) commits and pushes nothing
+        for _ in 0..2 {
+            let mut record = ChainRecord::start("test-flake", "lib", false, false).unwrap();
+            run_chain(&ws, &ResolvedRepos::default(), &chain, &opts, &mut record).unwrap();
+            assert!(record.journal.entries.is_empty());
+            assert_eq!(head(), before);
+        }
+
+        let _ = std::fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn test_flake_update_args_append_nix_args_after_inputs() {
+        let inputs = vec!["nixpkgs".to_string()];
+        let nix_args = vec!["--accept-flake-config".to_string(), "--option".to_string(), "sandbox".to_string(), "false".to_string()];
+        assert_eq!(
+            flake_update_args(&inputs, &nix_args),
+            ["flake", "update", "nixpkgs", "--accept-flake-config", "--option", "sandbox", "false"]
+        );
+        assert_eq!(flake_update_args(&inputs, &[]), ["flake", "update", "nixpkgs"]);
+    }
+
+    #[test]
+    fn test_commit_author_overrides_repo_user() {
+        assert_eq!(
+            parse_commit_author("tend bot <bot@example.com>").unwrap(),
+            ("tend bot", "bot@example.com")
+        );
+        assert!(parse_commit_author("bot@example.com").is_err());
+        assert!(parse_commit_author("<bot@example.com>").is_err());
+
+        assert_eq!(commit_args("chore: update a", None), vec!["commit", "-m", "chore: update a"]);
+        assert_eq!(
+            commit_args("chore: update a", Some(("tend bot", "bot@example.com"))),
+            vec![
+                "-c",
+                "user.name=tend bot",
+                "-c",
+                "user.email=bot@example.com",
+                "commit",
+                "-m",
+                "chore: update a",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_commit_groups() {
+        let inputs = vec!["lib".to_string(), "nixpkgs".to_string()];
+        assert_eq!(
+            commit_groups(&inputs, FlakeCommitGranularity::PerRepo),
+            vec![inputs.clone()]
+        );
+        assert_eq!(
+            commit_groups(&inputs, FlakeCommitGranularity::PerInput),
+            vec![vec!["lib".to_string()], vec!["nixpkgs".to_string()]]
+        );
+    }
+
+    #[test]
+    fn test_check_chain_inputs_reports_undeclared_inputs() {
+        let base = std::env::temp_dir().join(format!("tend-test-inputs-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&base);
+        std::fs::create_dir_all(base.join("app")).unwrap();
+        std::fs::write(
+            base.join("app/flake.lock"),
+            r#"{"nodes": {"root": {"inputs": {"lib": "lib", "nixpkgs": "nixpkgs"}}}, "root": "root", "version": 7}"#,
+        )
+        .unwrap();
+
+        let mut ws = make_workspace();
+        ws.base_dir = base.to_string_lossy().to_string();
+        // `nix` has no clone, so it can't be checked
+        ws.flake_deps = deps(&[("app", &["lib", "util"]), ("nix", &["app", "other"])]);
+        let chain = compute_update_chain("lib", &ws.flake_deps).unwrap();
+
+        let missing = check_chain_inputs(&ws, &ResolvedRepos::default(), &chain).unwrap();
+        assert_eq!(missing, vec![MissingInput { repo: "app".to_string(), input: "util".to_string() }]);
+
+        let _ = std::fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn test_dependency_order_puts_upstreams_first() {
+        let flake_deps = deps(&[
+            ("nix", &["app", "lib", "nixpkgs"]),
+            ("app", &["lib"]),
+            ("lib", &["nixpkgs"]),
+            ("a", &["b"]),
+            ("b", &["a"]),
+        ]);
+        assert_eq!(dependency_order(&flake_deps), ["lib", "app", "nix", "a", "b"]);
+    }
+
+    #[test]
+    fn test_flake_status_reports_stale_inputs() {
+        let base = std::env::temp_dir().join(format!("tend-test-flake-status-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&base);
+        std::fs::create_dir_all(base.join("app")).unwrap();
+        let lock = serde_json::json!({
+            "root": "root",
+            "nodes": {
+                "root": {"inputs": {"lib": "lib", "nixpkgs": "nixpkgs", "utils": ["lib", "utils"]}},
+                "lib": {
+                    "locked": {"type": "github", "owner": "acme", "repo": "lib", "rev": "old"},
+                    "original": {"type": "github", "owner": "acme", "repo": "lib"}
+                },
+                "nixpkgs": {
+                    "locked": {"type": "github", "owner": "NixOS", "repo": "nixpkgs", "rev": "same"},
+                    "original": {"type": "github", "owner": "NixOS", "repo": "nixpkgs", "ref": "nixos-unstable"}
+                }
+            }
+        });
+        std::fs::write(base.join("app/flake.lock"), lock.to_string()).unwrap();
+
+        let mut ws = make_workspace();
+        ws.base_dir = base.to_string_lossy().to_string();
+        ws.flake_deps = deps(&[("app", &["lib", "nixpkgs", "utils"]), ("web", &["lib"])]);
+        let mut asked = Vec::new();
+        let statuses = flake_status_with(&ws, &ResolvedRepos::default(), |flake_ref| {
+            asked.push(flake_ref.to_string());
+            Ok(if flake_ref.starts_with("github:acme/lib") { "new" } else { "same" }.to_string())
+        })
+        .unwrap();
+
+        assert_eq!(asked, ["github:acme/lib", "github:NixOS/nixpkgs/nixos-unstable"]);
+        assert_eq!(statuses.len(), 2);
+        assert_eq!(
+            statuses[0].stale,
+            vec![StaleInput { input: "lib".to_string(), locked: "old".to_string(), upstream: "new".to_string() }]
+        );
+        assert_eq!(statuses[0].unchecked, vec![("utils".to_string(), "follows another input".to_string())]);
+        assert!(statuses[1].no_lock);
+
+        let _ = std::fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn test_original_flake_ref() {
+        let git = serde_json::json!({"type": "git", "url": "https://git.example.com/x.git", "ref": "main"});
+        assert_eq!(original_flake_ref(&git).unwrap(), "git+https://git.example.com/x.git?ref=main");
+        assert_eq!(original_flake_ref(&serde_json::json!({"type": "path", "path": "/x"})), None);
+    }
+
+    #[test]
+    fn test_lock_changes() {
+        let old = r#"{"nodes": {
+            "nixpkgs": {"locked": {"rev": "aaa"}},
+            "lib": {"locked": {"rev": "bbb"}},
+            "gone": {"locked": {"narHash": "sha256-x"}},
+            "root": {"inputs": {"nixpkgs": "nixpkgs"}}
+        }, "root": "root", "version": 7}"#;
+        let new = r#"{"nodes": {
+            "nixpkgs": {"locked": {"rev": "aaa"}},
+            "lib": {"locked": {"rev": "ccc"}},
+            "added": {"locked": {"rev": "ddd"}},
+            "root": {"inputs": {"nixpkgs": "nixpkgs"}}
+        }, "root": "root", "version": 7}"#;
+
+        let changes = lock_changes(old, new).unwrap();
+        let rev = |r: &str| Some(r.to_string());
+        assert_eq!(
+            changes,
+            vec![
+                LockChange { input: "added".to_string(), old: None, new: rev("ddd") },
+                LockChange { input: "gone".to_string(), old: rev("sha256-x"), new: None },
+                LockChange { input: "lib".to_string(), old: rev("bbb"), new: rev("ccc") },
+            ]
+        );
+        assert_eq!(lock_changes("", new).unwrap().len(), 3);
+        assert!(lock_changes(new, new).unwrap().is_empty());
+        assert!(lock_changes("{not json", new).is_err());
+    }
+
+    #[test]
+    fn test_parse_remote_head() {
+        assert_eq!(parse_remote_head("refs/remotes/origin/main\n", "origin").as_deref(), Some("main"));
+        assert_eq!(
+            parse_remote_head("refs/remotes/origin/release/v2", "origin").as_deref(),
+            Some("release/v2")
+        );
+        assert_eq!(parse_remote_head("refs/remotes/origin/", "origin"), None);
+        assert_eq!(parse_remote_head("refs/heads/main", "origin"), None);
+        assert_eq!(parse_remote_head("refs/remotes/upstream/main", "upstream").as_deref(), Some("main"));
+        assert_eq!(parse_remote_head("refs/remotes/originals/main", "origin"), None);
+    }
+
+    #[test]
+    fn test_compute_update_chain_no_dependents() {
+        let flake_deps = deps(&[("app", &["lib"])]);
+        assert!(compute_update_chain("other", &flake_deps).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_compute_update_chain_detects_cycle() {
+        let flake_deps = deps(&[("a", &["b", "root"]), ("b", &["a"])]);
+        assert!(compute_update_chain("root", &flake_deps).is_err());
+    }
+}
@@ -0,0 +1,121 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Record of the commits pushed by the last flake-update chain in a workspace,
+/// kept so `tend flake-update --rollback` can undo them.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct FlakeJournal {
+    /// Repo whose push triggered the chain
+    pub changed: String,
+    /// RFC 3339 time the chain ran
+    pub timestamp: String,
+    /// Pushed commits in the order they were made
+    #[serde(default)]
+    pub entries: Vec<JournalEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct JournalEntry {
+    pub repo: String,
+    /// HEAD before the lock update was committed
+    pub before: String,
+    /// The pushed lock update commit
+    pub after: String,
+}
+
+/// Steps finished by the most recent chain in a workspace. Removed once the
+/// chain completes, so its presence means the chain was interrupted and
+/// `tend flake-update --resume` can pick up where it stopped.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct ChainProgress {
+    /// Repo whose push triggered the chain
+    pub changed: String,
+    /// RFC 3339 time the chain started (matches its journal's `timestamp`)
+    pub started: String,
+    /// Repos whose step finished, pushed or unchanged
+    #[serde(default)]
+    pub completed: Vec<String>,
+}
+
+/// Load the journal for a workspace, or `None` when no chain has been recorded.
+pub fn load(workspace_name: &str) -> Result<Option<FlakeJournal>> {
+    let path = journal_path(workspace_name);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("reading flake journal {}", path.display()))?;
+    let journal = serde_json::from_str(&content)
+        .with_context(|| format!("parsing flake journal {}", path.display()))?;
+    Ok(Some(journal))
+}
+
+/// Replace the workspace's journal with `journal`.
+pub fn save(workspace_name: &str, journal: &FlakeJournal) -> Result<()> {
+    let dir = journal_dir();
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("creating flake journal dir {}", dir.display()))?;
+
+    let path = journal_path(workspace_name);
+    std::fs::write(&path, serde_json::to_string_pretty(journal)?)
+        .with_context(|| format!("writing flake journal {}", path.display()))?;
+    Ok(())
+}
+
+/// Forget the workspace's journal (after a completed rollback).
+pub fn clear(workspace_name: &str) -> Result<()> {
+    let path = journal_path(workspace_name);
+    if path.exists() {
+        std::fs::remove_file(&path)
+            .with_context(|| format!("removing flake journal {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Load the progress of an interrupted chain, or `None` when the last chain finished.
+pub fn load_progress(workspace_name: &str) -> Result<Option<ChainProgress>> {
+    let path = progress_path(workspace_name);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("reading chain progress {}", path.display()))?;
+    let progress = serde_json::from_str(&content)
+        .with_context(|| format!("parsing chain progress {}", path.display()))?;
+    Ok(Some(progress))
+}
+
+/// Replace the workspace's chain progress with `progress`.
+pub fn save_progress(workspace_name: &str, progress: &ChainProgress) -> Result<()> {
+    let dir = journal_dir();
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("creating flake journal dir {}", dir.display()))?;
+
+    let path = progress_path(workspace_name);
+    std::fs::write(&path, serde_json::to_string_pretty(progress)?)
+        .with_context(|| format!("writing chain progress {}", path.display()))?;
+    Ok(())
+}
+
+/// Forget the workspace's chain progress (after the chain completed).
+pub fn clear_progress(workspace_name: &str) -> Result<()> {
+    let path = progress_path(workspace_name);
+    if path.exists() {
+        std::fs::remove_file(&path)
+            .with_context(|| format!("removing chain progress {}", path.display()))?;
+    }
+    Ok(())
+}
+
+fn journal_dir() -> PathBuf {
+    crate::cache::cache_dir().join("flake-journal")
+}
+
+fn journal_path(workspace_name: &str) -> PathBuf {
+    journal_dir().join(format!("{workspace_name}.json"))
+}
+
+fn progress_path(workspace_name: &str) -> PathBuf {
+    journal_dir().join(format!("{workspace_name}.progress.json"))
+}
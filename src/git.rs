@@ -2,6 +2,9 @@ use anyhow::{Context, Result};
 use std::path::Path;
 use std::process::Command;
 
+use crate::exit::git_err;
+use crate::runner;
+
 /// Trait abstracting git operations for testability.
 pub trait GitOps: Send + Sync {
     /// Stage a file for commit.
@@ -19,8 +22,8 @@ pub trait GitOps: Send + Sync {
     /// Get the current branch name.
     fn current_branch(&self, repo_dir: &Path) -> Result<String>;
 
-    /// Pull from remote for the given branch.
-    fn pull(&self, repo_dir: &Path, branch: &str) -> Result<()>;
+    /// Pull the given branch from `remote`.
+    fn pull(&self, repo_dir: &Path, remote: &str, branch: &str) -> Result<()>;
 
     /// Check if the working tree is clean (no uncommitted changes).
     fn is_clean(&self, repo_dir: &Path) -> Result<bool>;
@@ -31,15 +34,16 @@ pub struct SystemGitOps;
 
 impl GitOps for SystemGitOps {
     fn add(&self, repo_dir: &Path, file_path: &Path) -> Result<()> {
-        let output = Command::new("git")
-            .args(["add", &file_path.to_string_lossy()])
-            .current_dir(repo_dir)
-            .output()
-            .context("running git add")?;
+        let output = runner::output(
+            Command::new("git")
+                .args(["add", &file_path.to_string_lossy()])
+                .current_dir(repo_dir),
+        )
+        .context("running git add")?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("git add failed: {stderr}");
+            return Err(git_err!("git add failed: {stderr}"));
         }
         Ok(())
     }
@@ -54,29 +58,31 @@ impl GitOps for SystemGitOps {
     }
 
     fn commit(&self, repo_dir: &Path, message: &str) -> Result<()> {
-        let output = Command::new("git")
-            .args(["commit", "-m", message])
-            .current_dir(repo_dir)
-            .output()
-            .context("running git commit")?;
+        let output = runner::output(
+            Command::new("git")
+                .args(["commit", "-m", message])
+                .current_dir(repo_dir),
+        )
+        .context("running git commit")?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("git commit failed: {stderr}");
+            return Err(git_err!("git commit failed: {stderr}"));
         }
         Ok(())
     }
 
     fn push(&self, repo_dir: &Path) -> Result<()> {
-        let output = Command::new("git")
-            .args(["push"])
-            .current_dir(repo_dir)
-            .output()
-            .context("running git push")?;
+        let output = runner::output(
+            Command::new("git")
+                .args(["push"])
+                .current_dir(repo_dir),
+        )
+        .context("running git push")?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("git push failed: {stderr}");
+            return Err(git_err!("git push failed: {stderr}"));
         }
         Ok(())
     }
@@ -90,21 +96,22 @@ impl GitOps for SystemGitOps {
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("git rev-parse failed: {stderr}");
+            return Err(git_err!("git rev-parse failed: {stderr}"));
         }
         Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
     }
 
-    fn pull(&self, repo_dir: &Path, branch: &str) -> Result<()> {
-        let output = Command::new("git")
-            .args(["pull", "origin", branch])
-            .current_dir(repo_dir)
-            .output()
-            .context("running git pull")?;
+    fn pull(&self, repo_dir: &Path, remote: &str, branch: &str) -> Result<()> {
+        let output = runner::output(
+            Command::new("git")
+                .args(["pull", remote, branch])
+                .current_dir(repo_dir),
+        )
+        .context("running git pull")?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("git pull failed: {stderr}");
+            return Err(git_err!("git pull failed: {stderr}"));
         }
         Ok(())
     }
@@ -118,7 +125,7 @@ impl GitOps for SystemGitOps {
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("git status failed: {stderr}");
+            return Err(git_err!("git status failed: {stderr}"));
         }
         Ok(output.stdout.is_empty())
     }
@@ -44,6 +44,7 @@ impl HttpGitHubClient {
 impl GitHubClient for HttpGitHubClient {
     async fn get_repo_head(&self, org: &str, repo: &str) -> Result<String> {
         use todoku::GitHubApi;
+        crate::provider::count_api_request();
         self.inner
             .get_repo_head(org, repo)
             .await
@@ -52,6 +53,7 @@ impl GitHubClient for HttpGitHubClient {
 
     async fn get_latest_tag(&self, org: &str, repo: &str) -> Result<Option<String>> {
         use todoku::GitHubApi;
+        crate::provider::count_api_request();
         self.inner
             .get_latest_tag(org, repo)
             .await
@@ -60,6 +62,7 @@ impl GitHubClient for HttpGitHubClient {
 
     async fn detect_repo_language(&self, org: &str, repo: &str) -> Result<Option<String>> {
         use todoku::GitHubApi;
+        crate::provider::count_api_request();
         self.inner
             .get_primary_language(org, repo)
             .await
@@ -73,6 +76,7 @@ impl GitHubClient for HttpGitHubClient {
         path: &str,
     ) -> Result<(String, u64, String)> {
         use todoku::GitHubApi;
+        crate::provider::count_api_request();
         let info = self
             .inner
             .get_file_info(org, repo, path)
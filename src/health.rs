@@ -0,0 +1,205 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Daemon loop state shared with the health server.
+pub type SharedState = Arc<Mutex<DaemonState>>;
+
+/// Apply `f` to the shared state (a poisoned lock is still usable: the state is plain data).
+pub fn update(state: &SharedState, f: impl FnOnce(&mut DaemonState)) {
+    f(&mut state.lock().unwrap_or_else(|e| e.into_inner()));
+}
+
+/// What the daemon loop has done so far, as served on `/status`.
+#[derive(Debug, Serialize)]
+pub struct DaemonState {
+    /// Number of the cycle in progress (or last finished)
+    pub cycle: u64,
+    /// RFC 3339 time the last cycle finished
+    pub last_cycle_at: Option<String>,
+    /// Error from the last config load, cleared once it loads again
+    pub config_error: Option<String>,
+    pub workspaces: BTreeMap<String, WorkspaceHealth>,
+    /// Last time a cycle started or finished; `/healthz` fails once it is stale
+    #[serde(skip)]
+    last_progress: Instant,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct WorkspaceHealth {
+    /// Cycle that produced `last_result`
+    pub last_cycle: u64,
+    /// `"ok"`, or the error that ended the workspace's last cycle
+    pub last_result: String,
+    pub consecutive_errors: u32,
+}
+
+impl Default for DaemonState {
+    fn default() -> Self {
+        Self {
+            cycle: 0,
+            last_cycle_at: None,
+            config_error: None,
+            workspaces: BTreeMap::new(),
+            last_progress: Instant::now(),
+        }
+    }
+}
+
+impl DaemonState {
+    pub fn cycle_started(&mut self, cycle: u64) {
+        self.cycle = cycle;
+        self.last_progress = Instant::now();
+    }
+
+    pub fn cycle_finished(&mut self) {
+        self.last_cycle_at = Some(chrono::Utc::now().to_rfc3339());
+        self.last_progress = Instant::now();
+    }
+
+    /// Record the outcome of one workspace's cycle.
+    pub fn record(&mut self, workspace: &str, cycle: u64, result: Result<(), String>) {
+        let health = self.workspaces.entry(workspace.to_string()).or_default();
+        health.last_cycle = cycle;
+        match result {
+            Ok(()) => {
+                health.last_result = "ok".to_string();
+                health.consecutive_errors = 0;
+            }
+            Err(e) => {
+                health.last_result = e;
+                health.consecutive_errors += 1;
+            }
+        }
+    }
+
+    /// The loop counts as alive while it has started or finished a cycle
+    /// within `max_quiet`.
+    fn is_alive(&self, max_quiet: Duration) -> bool {
+        self.last_progress.elapsed() <= max_quiet
+    }
+}
+
+/// How long the loop may go without starting or finishing a cycle before
+/// `/healthz` reports it stuck: three sleep intervals, and at least 10 minutes
+/// so a slow cycle isn't mistaken for a hung one.
+pub fn liveness_window(interval_secs: u64) -> Duration {
+    Duration::from_secs(interval_secs.saturating_mul(3).max(600))
+}
+
+/// Bind the health server. Done before the loop starts so a taken port fails fast.
+pub async fn bind(addr: SocketAddr) -> Result<TcpListener> {
+    TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("binding health server on {addr}"))
+}
+
+/// Serve `/healthz` and `/status` until the process exits.
+pub async fn serve(listener: TcpListener, state: SharedState, max_quiet: Duration) {
+    loop {
+        let Ok((stream, _)) = listener.accept().await else {
+            continue;
+        };
+        let state = Arc::clone(&state);
+        tokio::spawn(async move {
+            if let Err(e) = handle(stream, &state, max_quiet).await {
+                crate::display::errln!("daemon: health request failed: {e:#}");
+            }
+        });
+    }
+}
+
+async fn handle(mut stream: TcpStream, state: &Mutex<DaemonState>, max_quiet: Duration) -> Result<()> {
+    // Only the request line matters; headers and bodies are ignored
+    let mut buf = vec![0u8; 1024];
+    let n = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buf))
+        .await
+        .context("timed out reading request")??;
+    let request = String::from_utf8_lossy(&buf[..n]);
+    let path = request.split_whitespace().nth(1).unwrap_or("/");
+
+    let (code, content_type, body) = {
+        let state = state.lock().unwrap_or_else(|e| e.into_inner());
+        respond(path, &state, max_quiet)
+    };
+    let reason = match code {
+        200 => "OK",
+        404 => "Not Found",
+        _ => "Service Unavailable",
+    };
+    let response = format!(
+        "HTTP/1.1 {code} {reason}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Status code, content type, and body for a request path.
+fn respond(path: &str, state: &DaemonState, max_quiet: Duration) -> (u16, &'static str, String) {
+    match path {
+        "/healthz" if state.is_alive(max_quiet) => (200, "text/plain", "ok\n".to_string()),
+        "/healthz" => (503, "text/plain", "daemon loop stalled\n".to_string()),
+        "/status" => {
+            let body = serde_json::to_string_pretty(state).unwrap_or_else(|_| "{}".to_string());
+            (200, "application/json", body)
+        }
+        _ => (404, "text/plain", "not found\n".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_counts_consecutive_errors() {
+        let mut state = DaemonState::default();
+        state.record("ws", 1, Err("boom".to_string()));
+        state.record("ws", 2, Err("boom".to_string()));
+        assert_eq!(state.workspaces["ws"].consecutive_errors, 2);
+        assert_eq!(state.workspaces["ws"].last_result, "boom");
+
+        state.record("ws", 3, Ok(()));
+        assert_eq!(state.workspaces["ws"].consecutive_errors, 0);
+        assert_eq!(state.workspaces["ws"].last_cycle, 3);
+    }
+
+    #[test]
+    fn test_respond_routes() {
+        let mut state = DaemonState::default();
+        state.cycle_started(4);
+        state.record("ws", 4, Ok(()));
+
+        assert_eq!(respond("/healthz", &state, Duration::from_secs(60)).0, 200);
+        assert_eq!(respond("/healthz", &state, Duration::ZERO).0, 503);
+        assert_eq!(respond("/nope", &state, Duration::from_secs(60)).0, 404);
+
+        let (code, content_type, body) = respond("/status", &state, Duration::from_secs(60));
+        assert_eq!((code, content_type), (200, "application/json"));
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(json["cycle"], 4);
+        assert_eq!(json["workspaces"]["ws"]["last_result"], "ok");
+    }
+
+    #[tokio::test]
+    async fn test_serve_answers_over_tcp() {
+        let listener = bind("127.0.0.1:0".parse().unwrap()).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let state: SharedState = Arc::default();
+        tokio::spawn(serve(listener, state, Duration::from_secs(60)));
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(b"GET /healthz HTTP/1.1\r\nHost: x\r\n\r\n").await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.ends_with("ok\n"));
+    }
+}
@@ -0,0 +1,84 @@
+use anyhow::{Context, Result};
+use fs2::FileExt;
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+
+/// Env var set on child `tend` processes spawned while a workspace lock is held
+/// (e.g. `tend flake-update` during auto-propagate). A child whose workspace
+/// matches skips locking instead of deadlocking against its parent.
+pub const HELD_LOCK_ENV: &str = "TEND_HELD_LOCK";
+
+/// Advisory per-workspace lock preventing concurrent mutating tend runs.
+///
+/// Backed by `flock` on `~/.cache/tend/locks/{workspace}.lock`. The lock is
+/// released when the guard is dropped (or the process exits).
+pub struct WorkspaceLock {
+    file: Option<File>,
+}
+
+impl WorkspaceLock {
+    /// Try to take the lock for a workspace without blocking.
+    /// Returns `Ok(None)` if another tend process holds it.
+    pub fn try_acquire(workspace_name: &str) -> Result<Option<Self>> {
+        if std::env::var(HELD_LOCK_ENV).is_ok_and(|held| held == workspace_name) {
+            return Ok(Some(Self { file: None }));
+        }
+        try_acquire_at(&lock_path(workspace_name))
+    }
+}
+
+impl Drop for WorkspaceLock {
+    fn drop(&mut self) {
+        if let Some(ref file) = self.file {
+            let _ = file.unlock();
+        }
+    }
+}
+
+fn try_acquire_at(path: &Path) -> Result<Option<WorkspaceLock>> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("creating lock dir {}", parent.display()))?;
+    }
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)
+        .with_context(|| format!("opening lock file {}", path.display()))?;
+
+    match file.try_lock_exclusive() {
+        Ok(()) => Ok(Some(WorkspaceLock { file: Some(file) })),
+        Err(e) if e.kind() == fs2::lock_contended_error().kind() => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("locking {}", path.display())),
+    }
+}
+
+fn lock_dir() -> PathBuf {
+    crate::cache::cache_dir().join("locks")
+}
+
+fn lock_path(workspace_name: &str) -> PathBuf {
+    lock_dir().join(format!("{workspace_name}.lock"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_is_exclusive_until_dropped() {
+        let path = std::env::temp_dir()
+            .join("tend-test-lock")
+            .join(format!("ws-{}.lock", std::process::id()));
+
+        let first = try_acquire_at(&path).unwrap();
+        assert!(first.is_some());
+        assert!(try_acquire_at(&path).unwrap().is_none());
+
+        drop(first);
+        assert!(try_acquire_at(&path).unwrap().is_some());
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -1,25 +1,115 @@
 mod audit;
 mod cache;
+mod clone_failures;
 mod config;
 mod daemon;
 mod display;
+mod exit;
 mod flake;
+mod flake_journal;
 mod git;
 mod github;
+mod health;
+mod lock;
 mod provider;
+mod runner;
 mod sync;
+#[cfg(feature = "tui")]
+mod tui;
 mod watch;
 mod watch_cache;
 
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
+use std::future::Future;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 #[derive(Parser)]
 #[command(name = "tend", version, about = "Workspace repository manager")]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Maximum concurrent GitHub API requests across workspaces
+    #[arg(long, global = true, default_value_t = provider::DEFAULT_API_JOBS)]
+    api_jobs: usize,
+
+    /// Ignore the config's global_exclude for this run
+    #[arg(long, global = true)]
+    no_default_excludes: bool,
+
+    /// Print only warnings and errors (to stderr) besides the command's own output
+    #[arg(long, global = true)]
+    quiet: bool,
+
+    /// Print nothing but the command's own output, not even warnings or errors
+    #[arg(long, global = true, conflicts_with = "quiet")]
+    silent: bool,
+
+    /// Also print diagnostics, such as how many GitHub API requests sync/status made
+    #[arg(short, long, global = true, conflicts_with_all = ["quiet", "silent"])]
+    verbose: bool,
+
+    /// Prefix progress lines, summaries, and warnings with the time (also TEND_TIMESTAMPS=1)
+    #[arg(long, global = true)]
+    timestamps: bool,
+
+    /// Print the git/nix commands that would change repos (argv and cwd) instead of running them.
+    /// Commands with their own --dry-run (flake-update, rename-repo) keep its meaning
+    #[arg(long, global = true)]
+    dry_run: bool,
+}
+
+/// Which configured workspaces a command acts on.
+#[derive(Args, Debug, Clone, Default)]
+pub(crate) struct WorkspaceFilter {
+    /// Only act on one workspace (exact name, or a glob like team-a-*)
+    #[arg(long)]
+    workspace: Option<String>,
+
+    /// Skip a workspace by name (repeatable; ignored for the --workspace target)
+    #[arg(long = "exclude-workspace")]
+    exclude_workspace: Vec<String>,
+
+    /// Only include workspaces carrying this tag (repeatable; any listed tag matches)
+    #[arg(long)]
+    tag: Vec<String>,
+
+    /// With several --tag flags, require every tag instead of any
+    #[arg(long, requires = "tag")]
+    all_tags: bool,
+}
+
+impl WorkspaceFilter {
+    pub(crate) fn select<'a>(&self, workspaces: &'a [config::Workspace]) -> Vec<&'a config::Workspace> {
+        filter_workspaces(workspaces, self.workspace.as_deref(), &self.exclude_workspace, &self.tag, self.all_tags)
+    }
+}
+
+/// `--fail-on-empty`, for commands that resolve workspaces' repo lists.
+#[derive(Args, Debug, Clone, Copy, Default)]
+struct FailOnEmpty {
+    /// Exit nonzero (code 2) if a selected workspace resolves to no repos, before anything in it is touched
+    #[arg(long)]
+    fail_on_empty: bool,
+}
+
+/// `--base-dir`, which relocates the one workspace `--workspace` selects.
+#[derive(Args, Debug, Clone, Default)]
+struct BaseDirOverride {
+    /// Use this directory as the workspace's base_dir for this run (needs a --workspace matching one workspace)
+    #[arg(long, requires = "workspace", value_name = "PATH")]
+    base_dir: Option<String>,
+}
+
+impl BaseDirOverride {
+    fn apply(&self, workspaces: &mut [config::Workspace], filter: &WorkspaceFilter) -> Result<()> {
+        match self.base_dir {
+            Some(ref dir) => override_base_dir(workspaces, filter.workspace.as_deref(), dir),
+            None => Ok(()),
+        }
+    }
 }
 
 #[derive(Subcommand)]
@@ -30,17 +120,104 @@ enum Commands {
         #[arg(long)]
         config: Option<PathBuf>,
 
-        /// Only sync a specific workspace by name
+        #[command(flatten)]
+        filter: WorkspaceFilter,
+
+        #[command(flatten)]
+        base_dir: BaseDirOverride,
+
+        /// Bypass discovery cache and always hit the GitHub API
         #[arg(long)]
-        workspace: Option<String>,
+        refresh: bool,
+
+        /// Discard uncommitted changes in dirty repos (git reset --hard + git clean -fd)
+        #[arg(long, requires = "force", conflicts_with = "parallel_workspaces")]
+        clean: bool,
+
+        /// Confirm destructive operations such as --clean
+        #[arg(long)]
+        force: bool,
+
+        /// Remove and re-clone directories that are not valid git repos
+        #[arg(long)]
+        repair: bool,
+
+        /// Don't try repos that failed to clone on the last 3 syncs in a row
+        #[arg(long)]
+        skip_flaky: bool,
+
+        /// Extra argument for git clone, added after the workspace's extra_git_args
+        /// (repeatable; passed verbatim, e.g. --git-arg=--jobs=8)
+        #[arg(long = "git-arg", allow_hyphen_values = true)]
+        git_arg: Vec<String>,
+
+        /// Exit nonzero after the run if any clone failed (for CI)
+        #[arg(long)]
+        strict: bool,
+
+        /// Process up to N workspaces concurrently (default 4 when given without a value)
+        #[arg(long, num_args = 0..=1, default_missing_value = "4", value_name = "N")]
+        parallel_workspaces: Option<usize>,
+
+        /// Move clean repos no longer in config into base_dir/.archive/
+        #[arg(long)]
+        archive_moved: bool,
+
+        /// Converge to the config: archive repos no longer in it, then clone missing
+        /// ones. A dirty repo not in config blocks its workspace and fails the run.
+        #[arg(long, conflicts_with = "archive_moved")]
+        reconcile: bool,
+
+        /// Parallel clones/fetches (overrides the workspace's jobs and the config's default_jobs)
+        #[arg(long)]
+        jobs: Option<usize>,
+
+        #[command(flatten)]
+        fail_on_empty: FailOnEmpty,
+
+        /// Fail the workspace when its post_sync_hook exits nonzero (default: warn)
+        #[arg(long)]
+        strict_hooks: bool,
+    },
+
+    /// Fetch all remotes for existing repos (one-shot)
+    Fetch {
+        /// Path to config file
+        #[arg(long)]
+        config: Option<PathBuf>,
+
+        #[command(flatten)]
+        filter: WorkspaceFilter,
+
+        /// Bypass discovery cache and always hit the GitHub API
+        #[arg(long)]
+        refresh: bool,
+
+        /// Parallel clones/fetches (overrides the workspace's jobs and the config's default_jobs)
+        #[arg(long)]
+        jobs: Option<usize>,
+
+        #[command(flatten)]
+        fail_on_empty: FailOnEmpty,
+    },
+
+    /// Check that every clone's remote URL matches the config
+    Verify {
+        /// Path to config file
+        #[arg(long)]
+        config: Option<PathBuf>,
 
-        /// Suppress per-repo output, only show summary
+        /// Only verify a specific workspace (exact name, or a glob like team-a-*)
         #[arg(long)]
-        quiet: bool,
+        workspace: Option<String>,
 
         /// Bypass discovery cache and always hit the GitHub API
         #[arg(long)]
         refresh: bool,
+
+        /// Re-point mismatched remotes at the configured URL
+        #[arg(long)]
+        fix: bool,
     },
 
     /// Show repo status (clean/dirty/missing/unknown)
@@ -49,13 +226,82 @@ enum Commands {
         #[arg(long)]
         config: Option<PathBuf>,
 
-        /// Only show status for a specific workspace
+        #[command(flatten)]
+        filter: WorkspaceFilter,
+
+        #[command(flatten)]
+        base_dir: BaseDirOverride,
+
+        /// Bypass discovery cache and always hit the GitHub API
         #[arg(long)]
-        workspace: Option<String>,
+        refresh: bool,
+
+        /// List modified/untracked files under dirty repos
+        #[arg(long)]
+        show_changes: bool,
+
+        /// Show each repo's last commit date (one extra git call per repo)
+        #[arg(long)]
+        last_commit: bool,
+
+        /// Note repos with stashed changes (one extra git call per repo)
+        #[arg(long)]
+        stashes: bool,
+
+        /// Don't count submodule changes as dirty
+        #[arg(long)]
+        ignore_submodules: bool,
+
+        /// Don't count untracked files as dirty
+        #[arg(long)]
+        ignore_untracked: bool,
+
+        /// Stable machine format: one `<code> <workspace> <repo>` line per repo
+        /// (C clean, D dirty, M missing, U unknown, B broken, H detached, R mirror, I merge/rebase/bisect in progress)
+        #[arg(long, conflicts_with_all = ["show_changes", "last_commit", "stashes"])]
+        porcelain: bool,
+
+        /// Order repos by `name`, or by `status` to list the ones needing attention first
+        #[arg(long, value_enum, default_value_t = display::StatusSort::Name)]
+        sort: display::StatusSort,
+
+        /// `compact` blocks per workspace, or one aligned `table` across all of them
+        #[arg(long, value_enum, default_value_t = display::StatusFormat::Compact, conflicts_with = "porcelain")]
+        format: display::StatusFormat,
+
+        /// Process up to N workspaces concurrently (default 4 when given without a value)
+        #[arg(long, num_args = 0..=1, default_missing_value = "4", value_name = "N")]
+        parallel_workspaces: Option<usize>,
+
+        #[command(flatten)]
+        fail_on_empty: FailOnEmpty,
+    },
+
+    /// List repos with uncommitted work (dirty, or mid merge/rebase) by full path;
+    /// exits 1 if there are any
+    Dirty {
+        /// Path to config file
+        #[arg(long)]
+        config: Option<PathBuf>,
+
+        #[command(flatten)]
+        filter: WorkspaceFilter,
 
         /// Bypass discovery cache and always hit the GitHub API
         #[arg(long)]
         refresh: bool,
+
+        /// List the modified/untracked files under each repo
+        #[arg(long)]
+        show_changes: bool,
+
+        /// Don't count submodule changes as dirty
+        #[arg(long)]
+        ignore_submodules: bool,
+
+        /// Don't count untracked files as dirty
+        #[arg(long)]
+        ignore_untracked: bool,
     },
 
     /// List configured repos
@@ -64,9 +310,36 @@ enum Commands {
         #[arg(long)]
         config: Option<PathBuf>,
 
-        /// Only list repos for a specific workspace
+        #[command(flatten)]
+        filter: WorkspaceFilter,
+
+        #[command(flatten)]
+        base_dir: BaseDirOverride,
+
+        /// Bypass discovery cache and always hit the GitHub API
         #[arg(long)]
-        workspace: Option<String>,
+        refresh: bool,
+
+        /// Render workspaces and repos as a tree grouped by base_dir
+        #[arg(long)]
+        tree: bool,
+
+        /// Only show drift: `+ repo` configured but missing, `- repo` on disk but not configured
+        #[arg(long, conflicts_with = "tree")]
+        diff: bool,
+
+        #[command(flatten)]
+        fail_on_empty: FailOnEmpty,
+    },
+
+    /// Report present repos and disk usage per workspace, largest first
+    Du {
+        /// Path to config file
+        #[arg(long)]
+        config: Option<PathBuf>,
+
+        #[command(flatten)]
+        filter: WorkspaceFilter,
 
         /// Bypass discovery cache and always hit the GitHub API
         #[arg(long)]
@@ -78,21 +351,109 @@ enum Commands {
         /// GitHub org name
         org: String,
 
-        /// Provider (only github supported)
+        /// Provider (github or sourcehut)
         #[arg(long, default_value = "github")]
         provider: String,
+
+        /// Query only the GitHub org or user endpoint instead of trying org, then user
+        #[arg(long, value_enum, default_value_t = config::OwnerKind::Auto)]
+        kind: config::OwnerKind,
+
+        /// Emit a workspace config block pinning the discovered repos
+        #[arg(long)]
+        as_config: bool,
+
+        /// Write the generated config to a file instead of stdout
+        #[arg(long, requires = "as_config")]
+        output: Option<PathBuf>,
+
+        /// Print {org, repos, count} as JSON instead of the human listing
+        #[arg(long, conflicts_with = "as_config")]
+        json: bool,
     },
 
-    /// Run as a persistent daemon — sync + fetch on interval
-    Daemon {
+    /// Clone one repo outside any workspace, with tend's token handling
+    Clone {
+        /// Git URL, or owner/repo on github.com
+        url: String,
+
+        /// Directory to clone into (default: the repo name)
+        dir: Option<PathBuf>,
+
+        /// Expand owner/repo to an SSH URL instead of HTTPS
+        #[arg(long)]
+        ssh: bool,
+
+        /// Shallow clone with this many commits
+        #[arg(long)]
+        depth: Option<u32>,
+
+        /// Also clone submodules
+        #[arg(long)]
+        submodules: bool,
+    },
+
+    /// Print (or open in a browser) a repo's web URL
+    Open {
+        /// Repo name
+        repo: String,
+
         /// Path to config file
         #[arg(long)]
         config: Option<PathBuf>,
 
-        /// Only sync a specific workspace by name
+        /// Only look in a specific workspace (exact name, or a glob like team-a-*)
         #[arg(long)]
         workspace: Option<String>,
 
+        /// Open the URL in the default browser instead of printing it
+        #[arg(long)]
+        web: bool,
+
+        /// Bypass discovery cache and always hit the GitHub API
+        #[arg(long)]
+        refresh: bool,
+    },
+
+    /// Interactive status browser (navigate, refresh, sync/fetch/open)
+    #[cfg(feature = "tui")]
+    Tui {
+        /// Path to config file
+        #[arg(long)]
+        config: Option<PathBuf>,
+
+        #[command(flatten)]
+        filter: WorkspaceFilter,
+
+        /// Bypass discovery cache and always hit the GitHub API
+        #[arg(long)]
+        refresh: bool,
+    },
+
+    /// Show the authenticated GitHub user and token scopes
+    Whoami,
+
+    /// Show the remaining GitHub API budget (core and search)
+    Ratelimit,
+
+    /// Print the JSON Schema for the config file
+    Schema,
+
+    /// Inspect the config file tend resolves and loads
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// Run as a persistent daemon — sync + fetch on interval
+    Daemon {
+        /// Path to config file
+        #[arg(long)]
+        config: Option<PathBuf>,
+
+        #[command(flatten)]
+        filter: WorkspaceFilter,
+
         /// Sync interval in seconds
         #[arg(long, default_value = "300")]
         interval: u64,
@@ -101,13 +462,29 @@ enum Commands {
         #[arg(long, default_value = "true")]
         fetch: bool,
 
-        /// Suppress per-repo output
-        #[arg(long)]
-        quiet: bool,
-
         /// Path to file containing GitHub token (for launchd environments)
         #[arg(long)]
         github_token_file: Option<PathBuf>,
+
+        /// POST workspace errors to this webhook (overrides the config's notify_webhook)
+        #[arg(long)]
+        notify_webhook: Option<String>,
+
+        /// Serve /healthz (liveness) and /status (JSON loop state) over HTTP on this port
+        #[arg(long)]
+        health_port: Option<u16>,
+
+        /// Address the health server binds (use 0.0.0.0 for Kubernetes probes)
+        #[arg(long, default_value = "127.0.0.1", requires = "health_port")]
+        health_bind: std::net::IpAddr,
+
+        /// Periodically run `git maintenance run --auto` in clean repos
+        #[arg(long)]
+        maintenance: bool,
+
+        /// Cycles between maintenance passes
+        #[arg(long, default_value = "12", requires = "maintenance")]
+        maintenance_every: u64,
     },
 
     /// Run watch cycle once (detect new versions)
@@ -116,7 +493,7 @@ enum Commands {
         #[arg(long)]
         config: Option<PathBuf>,
 
-        /// Only watch a specific workspace
+        /// Only watch a specific workspace (exact name, or a glob like team-a-*)
         #[arg(long)]
         workspace: Option<String>,
 
@@ -126,7 +503,58 @@ enum Commands {
     },
 
     /// Generate a starter config file
-    Init,
+    Init {
+        /// Config file format (the file extension follows it)
+        #[arg(long, value_enum, default_value_t)]
+        format: config::ConfigFormat,
+    },
+
+    /// Add a repo to a workspace's extra_repos in the config file
+    AddRepo {
+        /// Workspace name
+        workspace: String,
+
+        /// Repo name
+        repo: String,
+
+        /// Path to config file
+        #[arg(long)]
+        config: Option<PathBuf>,
+
+        /// Clone the repo right away
+        #[arg(long)]
+        sync: bool,
+    },
+
+    /// Add a repo to a workspace's exclude list in the config file
+    ExcludeRepo {
+        /// Workspace name
+        workspace: String,
+
+        /// Repo name
+        repo: String,
+
+        /// Path to config file
+        #[arg(long)]
+        config: Option<PathBuf>,
+    },
+
+    /// Rename a repo across every workspace reference in the config file
+    RenameRepo {
+        /// Current repo name
+        old: String,
+
+        /// New repo name
+        new: String,
+
+        /// Path to config file
+        #[arg(long)]
+        config: Option<PathBuf>,
+
+        /// Show the edits without writing the file
+        #[arg(long)]
+        dry_run: bool,
+    },
 
     /// View the structured audit log
     AuditLog {
@@ -147,17 +575,28 @@ enum Commands {
         since: Option<String>,
     },
 
+    /// Show which flake_deps repos have inputs locked behind their upstream
+    FlakeStatus {
+        /// Path to config file
+        #[arg(long)]
+        config: Option<PathBuf>,
+
+        /// Only check a specific workspace (exact name, or a glob like team-a-*)
+        #[arg(long)]
+        workspace: Option<String>,
+    },
+
     /// Propagate nix flake update through the dependency chain
     FlakeUpdate {
         /// Repo that was just pushed (trigger)
-        #[arg(long)]
-        changed: String,
+        #[arg(long, required_unless_present = "rollback")]
+        changed: Option<String>,
 
         /// Path to config file
         #[arg(long)]
         config: Option<PathBuf>,
 
-        /// Only process a specific workspace
+        /// Only process a specific workspace (exact name, or a glob like team-a-*)
         #[arg(long)]
         workspace: Option<String>,
 
@@ -165,61 +604,606 @@ enum Commands {
         #[arg(long)]
         dry_run: bool,
 
-        /// Suppress per-step output
+        /// With --dry-run, print each workspace's chain as a JSON line instead
+        #[arg(long, requires = "dry_run")]
+        json: bool,
+
+        /// Update one repo at a time instead of running independent repos in parallel
         #[arg(long)]
-        quiet: bool,
+        sequential: bool,
+
+        /// Refuse to update repos not checked out on the remote's default branch
+        #[arg(long)]
+        require_default_branch: bool,
+
+        /// Skip steps an interrupted run of the same --changed chain already finished
+        #[arg(long)]
+        resume: bool,
+
+        /// Show each repo's flake.lock input changes and ask y/N before committing and pushing
+        #[arg(long)]
+        interactive: bool,
+
+        /// Commit each flake input separately, then push once (overrides flake_commit_granularity)
+        #[arg(long, conflicts_with = "commit_once")]
+        commit_each: bool,
+
+        /// Commit all of a repo's inputs together (overrides flake_commit_granularity)
+        #[arg(long)]
+        commit_once: bool,
+
+        /// Undo the last recorded chain: print the reset/push commands per repo
+        #[arg(long, conflicts_with_all = ["changed", "resume", "interactive", "commit_each", "commit_once"])]
+        rollback: bool,
+
+        /// With --rollback, run the commands (git reset --hard + force-push)
+        #[arg(long, requires = "rollback")]
+        force: bool,
+
+        /// Only pass these inputs to `nix flake update` (repeatable); repos left with none are skipped
+        #[arg(long = "input", value_name = "NAME", conflicts_with = "rollback")]
+        inputs: Vec<String>,
+
+        /// Extra argument for `nix flake update`, added after the workspace's flake_update_args
+        /// (repeatable; passed verbatim, e.g. --nix-arg=--accept-flake-config)
+        #[arg(long = "nix-arg", allow_hyphen_values = true, conflicts_with = "rollback")]
+        nix_arg: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print the resolved config path and whether it exists
+    Path {
+        /// Path to config file
+        #[arg(long)]
+        config: Option<PathBuf>,
+    },
+
+    /// Print the config as deserialized, with defaults filled in
+    Show {
+        /// Path to config file
+        #[arg(long)]
+        config: Option<PathBuf>,
     },
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> std::process::ExitCode {
     let cli = Cli::parse();
+    display::set_verbosity(match (cli.silent, cli.quiet, cli.verbose) {
+        (true, _, _) => display::Verbosity::Silent,
+        (_, true, _) => display::Verbosity::Quiet,
+        (_, _, true) => display::Verbosity::Verbose,
+        _ => display::Verbosity::Normal,
+    });
+    // The daemon already stamps its cycle lines; don't stamp them twice
+    let timestamps = cli.timestamps
+        || display::timestamps_from_env(std::env::var(display::TIMESTAMPS_ENV).ok().as_deref());
+    display::set_timestamps(timestamps && !matches!(cli.command, Commands::Daemon { .. }));
+    runner::set_dry_run(cli.dry_run);
+    match run(cli).await {
+        Ok(()) => std::process::ExitCode::SUCCESS,
+        Err(e) => {
+            // Same report anyhow prints when main returns an error
+            if !exit::is_quiet(&e) {
+                display::errln!("Error: {e:?}");
+            }
+            std::process::ExitCode::from(exit::exit_code(&e))
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let api_limiter = Arc::new(tokio::sync::Semaphore::new(cli.api_jobs.max(1)));
+    let quiet = display::verbosity() < display::Verbosity::Normal;
 
     match cli.command {
         Commands::Sync {
             config: config_path,
-            workspace: ws_filter,
-            quiet,
+            filter,
+            base_dir,
             refresh,
+            clean,
+            force: _,
+            repair,
+            skip_flaky,
+            git_arg,
+            strict,
+            parallel_workspaces,
+            archive_moved,
+            reconcile,
+            jobs,
+            fail_on_empty,
+            strict_hooks,
         } => {
-            let cfg = load_config(config_path.as_deref())?;
-            for ws in filter_workspaces(&cfg.workspaces, ws_filter.as_deref()) {
-                let repos = sync::resolve_repos(ws, refresh).await?;
-                let (cloned, present) = sync::sync_repos(ws, &repos, quiet).await?;
-                if !quiet || cloned > 0 {
-                    display::print_sync_summary(&ws.name, cloned, present);
+            let mut cfg = load_config(config_path.as_deref(), !cli.no_default_excludes)?;
+            base_dir.apply(&mut cfg.workspaces, &filter)?;
+            let workspaces: Vec<config::Workspace> =
+                filter.select(&cfg.workspaces)
+                    .into_iter()
+                    .map(|ws| {
+                        let mut ws = ws.clone();
+                        ws.extra_git_args.extend(git_arg.iter().cloned());
+                        ws.jobs = jobs.or(ws.jobs);
+                        ws
+                    })
+                    .collect();
+            let mut failures = Vec::new();
+            let mut failed_workspaces = 0usize;
+            let mut blocked_workspaces = 0usize;
+            let claims = Arc::new(sync::RepoClaims::default());
+            let empty = Arc::new(sync::EmptyWorkspaces::new(fail_on_empty.fail_on_empty));
+            // Archiving checks every configured workspace sharing a base dir, selected or not
+            let all_workspaces = Arc::new(cfg.workspaces.clone());
+
+            if let Some(limit) = parallel_workspaces {
+                // Each workspace's progress and warnings print in one piece when it finishes
+                let results = run_workspaces_parallel(&workspaces, limit, |ws| {
+                    let api_limiter = Arc::clone(&api_limiter);
+                    let claims = Arc::clone(&claims);
+                    let empty = Arc::clone(&empty);
+                    let all_workspaces = Arc::clone(&all_workspaces);
+                    async move {
+                        let Some(_lock) = lock::WorkspaceLock::try_acquire(&ws.name)? else {
+                            return Ok(None);
+                        };
+                        let repos = sync::resolve_repos(&ws, refresh, &api_limiter).await?;
+                        empty.check(&ws, &repos)?;
+                        let archived = if reconcile {
+                            let known = sync::KnownRepos::shared(&ws, &repos, &all_workspaces, &api_limiter).await?;
+                            let archived = sync::reconcile_unknown_repos(&ws, &known)?;
+                            if !archived.dirty.is_empty() {
+                                return Ok(Some((Some(archived), sync::SyncOutcome::default(), Ok(()))));
+                            }
+                            Some(archived)
+                        } else if archive_moved {
+                            let known = sync::KnownRepos::shared(&ws, &repos, &all_workspaces, &api_limiter).await?;
+                            Some(sync::archive_unknown_repos(&ws, &known)?)
+                        } else {
+                            None
+                        };
+                        let repos = claims.claim(&ws, &repos)?;
+                        let outcome = sync::sync_repos(&ws, &repos, sync::SyncOpts { quiet, repair, skip_flaky }).await?;
+                        let hook = sync::run_post_sync_hook(&ws, outcome.cloned, strict_hooks);
+                        Ok(Some((archived, outcome, hook)))
+                    }
+                })
+                .await;
+                for (name, result) in results {
+                    match result {
+                        Ok(Some((archived, outcome, hook))) => {
+                            if let Some(ref archived) = archived {
+                                display::print_archive_summary(&name, archived);
+                            }
+                            match archived {
+                                Some(ref archived) if reconcile => {
+                                    display::print_reconcile_summary(&name, outcome.cloned, archived);
+                                    if !archived.dirty.is_empty() {
+                                        blocked_workspaces += 1;
+                                    }
+                                }
+                                _ => display::print_sync_summary(&name, outcome.cloned, outcome.present),
+                            }
+                            failures.extend(outcome.failed.into_iter().map(|f| (name.clone(), f)));
+                            if let Err(e) = hook {
+                                display::print_workspace_error(&name, &e);
+                                failed_workspaces += 1;
+                            }
+                        }
+                        Ok(None) => display::print_workspace_locked(&name),
+                        Err(e) => {
+                            display::print_workspace_error(&name, &e);
+                            failed_workspaces += 1;
+                        }
+                    }
                 }
+            } else {
+                for ws in &workspaces {
+                    let Some(_lock) = lock::WorkspaceLock::try_acquire(&ws.name)? else {
+                        display::print_workspace_locked(&ws.name);
+                        continue;
+                    };
+                    let repos = sync::resolve_repos(ws, refresh, &api_limiter).await?;
+                    empty.check(ws, &repos)?;
+                    let reconciled = if reconcile {
+                        let known = sync::KnownRepos::shared(ws, &repos, &all_workspaces, &api_limiter).await?;
+                        let archived = sync::reconcile_unknown_repos(ws, &known)?;
+                        display::print_archive_summary(&ws.name, &archived);
+                        if !archived.dirty.is_empty() {
+                            display::print_reconcile_summary(&ws.name, 0, &archived);
+                            blocked_workspaces += 1;
+                            continue;
+                        }
+                        Some(archived)
+                    } else {
+                        if archive_moved {
+                            let known = sync::KnownRepos::shared(ws, &repos, &all_workspaces, &api_limiter).await?;
+                            display::print_archive_summary(&ws.name, &sync::archive_unknown_repos(ws, &known)?);
+                        }
+                        None
+                    };
+                    // Archiving needs the full list; cloning and cleaning skip other workspaces' repos
+                    let repos = claims.claim(ws, &repos)?;
+                    if clean {
+                        let cleaned = sync::clean_repos(ws, &repos).await?;
+                        if cleaned > 0 {
+                            display::errln!("{}: reset {cleaned} dirty repos", ws.name);
+                        }
+                    }
+                    let outcome = sync::sync_repos(ws, &repos, sync::SyncOpts { quiet, repair, skip_flaky }).await?;
+                    let hook = sync::run_post_sync_hook(ws, outcome.cloned, strict_hooks);
+                    if let Some(ref archived) = reconciled {
+                        display::print_reconcile_summary(&ws.name, outcome.cloned, archived);
+                    } else {
+                        display::print_sync_summary(&ws.name, outcome.cloned, outcome.present);
+                    }
+                    failures.extend(outcome.failed.into_iter().map(|f| (ws.name.clone(), f)));
+                    // A failed strict hook fails this workspace, not the rest of the run
+                    if let Err(e) = hook {
+                        display::print_workspace_error(&ws.name, &e);
+                        failed_workspaces += 1;
+                    }
+                }
+            }
+            // An empty workspace under --fail-on-empty also counts as failed; report it as the config error it is
+            empty.finish()?;
+            if strict && !failures.is_empty() {
+                display::print_clone_failures(&failures);
+                return Err(exit::Failure::PartialSync.tag(anyhow::anyhow!(
+                    "{} repo(s) failed to clone", failures.len()
+                )));
+            }
+            if failed_workspaces > 0 {
+                return Err(exit::Failure::PartialSync.tag(anyhow::anyhow!(
+                    "{failed_workspaces} workspace(s) failed"
+                )));
+            }
+            if blocked_workspaces > 0 {
+                return Err(exit::Failure::PartialSync.tag(anyhow::anyhow!(
+                    "{blocked_workspaces} workspace(s) not reconciled: repos not in config have uncommitted changes"
+                )));
+            }
+            if display::verbosity() == display::Verbosity::Verbose {
+                display::print_api_usage(&provider::api_usage());
             }
         }
 
-        Commands::Status {
+        Commands::Fetch {
+            config: config_path,
+            filter,
+            refresh,
+            jobs,
+            fail_on_empty,
+        } => {
+            let cfg = load_config(config_path.as_deref(), !cli.no_default_excludes)?;
+            let claims = sync::RepoClaims::default();
+            let empty = sync::EmptyWorkspaces::new(fail_on_empty.fail_on_empty);
+            for ws in filter.select(&cfg.workspaces) {
+                let mut ws = ws.clone();
+                ws.jobs = jobs.or(ws.jobs);
+                let ws = &ws;
+                let Some(_lock) = lock::WorkspaceLock::try_acquire(&ws.name)? else {
+                    display::print_workspace_locked(&ws.name);
+                    continue;
+                };
+                let repos = sync::resolve_repos(ws, refresh, &api_limiter).await?;
+                empty.check(ws, &repos)?;
+                let repos = claims.claim(ws, &repos)?;
+                let (fetched, skipped) = sync::fetch_repos(ws, &repos, quiet).await?;
+                display::print_fetch_summary(&ws.name, fetched, skipped);
+            }
+        }
+
+        Commands::Verify {
             config: config_path,
             workspace: ws_filter,
             refresh,
+            fix,
+        } => {
+            let cfg = load_config(config_path.as_deref(), !cli.no_default_excludes)?;
+            for ws in filter_workspaces(&cfg.workspaces, ws_filter.as_deref(), &[], &[], false) {
+                let repos = sync::resolve_repos(ws, refresh, &api_limiter).await?;
+                let mismatches = sync::verify_remotes(ws, &repos, fix).await?;
+                display::print_remote_mismatches(&ws.name, ws.remote_name(), &mismatches);
+            }
+        }
+
+        Commands::Status {
+            config: config_path,
+            filter,
+            base_dir,
+            refresh,
+            show_changes,
+            last_commit,
+            stashes,
+            ignore_submodules,
+            ignore_untracked,
+            porcelain,
+            sort,
+            format,
+            parallel_workspaces,
+            fail_on_empty,
         } => {
-            let cfg = load_config(config_path.as_deref())?;
-            for ws in filter_workspaces(&cfg.workspaces, ws_filter.as_deref()) {
-                let repos = sync::resolve_repos(ws, refresh).await?;
-                let entries = sync::check_status(ws, &repos).await?;
-                display::print_status(&ws.name, &entries);
+            let mut cfg = load_config(config_path.as_deref(), !cli.no_default_excludes)?;
+            base_dir.apply(&mut cfg.workspaces, &filter)?;
+            let opts = sync::StatusOpts {
+                show_changes,
+                last_commit,
+                stashes,
+                ignore_submodules,
+                ignore_untracked,
+            };
+            let workspaces = filter.select(&cfg.workspaces);
+            let empty = Arc::new(sync::EmptyWorkspaces::new(fail_on_empty.fail_on_empty));
+            // The table needs every workspace's entries to size its columns
+            let mut table = Vec::new();
+            let mut print = |name: &str, mut entries: Vec<sync::RepoEntry>| {
+                display::sort_entries(&mut entries, sort);
+                if porcelain {
+                    display::print_status_porcelain(name, &entries)
+                } else if format == display::StatusFormat::Table {
+                    table.push((name.to_string(), entries));
+                } else {
+                    display::print_status(name, &entries)
+                }
+            };
+            if let Some(limit) = parallel_workspaces {
+                let workspaces: Vec<config::Workspace> = workspaces.into_iter().cloned().collect();
+                let results = run_workspaces_parallel(&workspaces, limit, |ws| {
+                    let api_limiter = Arc::clone(&api_limiter);
+                    let empty = Arc::clone(&empty);
+                    async move {
+                        let repos = sync::resolve_repos(&ws, refresh, &api_limiter).await?;
+                        empty.check(&ws, &repos)?;
+                        sync::check_status(&ws, &repos, &opts).await
+                    }
+                })
+                .await;
+                let mut failed_workspaces = 0usize;
+                for (name, result) in results {
+                    match result {
+                        Ok(entries) => print(&name, entries),
+                        Err(e) => {
+                            display::print_workspace_error(&name, &e);
+                            failed_workspaces += 1;
+                        }
+                    }
+                }
+                if !table.is_empty() {
+                    display::print_status_table(&table);
+                }
+                empty.finish()?;
+                if failed_workspaces > 0 {
+                    return Err(exit::Failure::PartialSync.tag(anyhow::anyhow!(
+                        "{failed_workspaces} workspace(s) failed"
+                    )));
+                }
+            } else {
+                for ws in workspaces {
+                    let repos = sync::resolve_repos(ws, refresh, &api_limiter).await?;
+                    empty.check(ws, &repos)?;
+                    let entries = sync::check_status(ws, &repos, &opts).await?;
+                    print(&ws.name, entries);
+                }
+                if !table.is_empty() {
+                    display::print_status_table(&table);
+                }
+            }
+            if display::verbosity() == display::Verbosity::Verbose {
+                display::print_api_usage(&provider::api_usage());
+            }
+        }
+
+        Commands::Dirty {
+            config: config_path,
+            filter,
+            refresh,
+            show_changes,
+            ignore_submodules,
+            ignore_untracked,
+        } => {
+            let cfg = load_config(config_path.as_deref(), !cli.no_default_excludes)?;
+            let opts = sync::StatusOpts {
+                show_changes,
+                ignore_submodules,
+                ignore_untracked,
+                ..Default::default()
+            };
+            let mut found = 0usize;
+            for ws in filter.select(&cfg.workspaces) {
+                let repos = sync::resolve_repos(ws, refresh, &api_limiter).await?;
+                let entries = sync::check_status(ws, &repos, &opts).await?;
+                let uncommitted = sync::uncommitted_work(ws, &repos, entries)?;
+                display::print_uncommitted(&ws.name, &uncommitted);
+                found += uncommitted.len();
+            }
+            if found > 0 {
+                // The listing is the report; just exit 1
+                return Err(exit::Quiet.into());
             }
         }
 
         Commands::List {
+            config: config_path,
+            filter,
+            base_dir,
+            refresh,
+            tree,
+            diff,
+            fail_on_empty,
+        } => {
+            let mut cfg = load_config(config_path.as_deref(), !cli.no_default_excludes)?;
+            base_dir.apply(&mut cfg.workspaces, &filter)?;
+            let mut resolved = Vec::new();
+            let empty = sync::EmptyWorkspaces::new(fail_on_empty.fail_on_empty);
+            for ws in filter.select(&cfg.workspaces) {
+                let repos = sync::resolve_repos(ws, refresh, &api_limiter).await?;
+                empty.check(ws, &repos)?;
+                if diff {
+                    let entries = sync::check_status(ws, &repos, &sync::StatusOpts::default()).await?;
+                    display::print_repo_drift(&ws.name, &sync::drift(&entries));
+                } else if tree {
+                    resolved.push((ws.base_dir.clone(), ws.name.clone(), repos.names().to_vec()));
+                } else {
+                    display::print_repo_list(&ws.name, repos.names());
+                }
+            }
+            if tree {
+                display::print_repo_tree(&resolved);
+            }
+        }
+
+        Commands::Du {
+            config: config_path,
+            filter,
+            refresh,
+        } => {
+            let cfg = load_config(config_path.as_deref(), !cli.no_default_excludes)?;
+            let mut rows = Vec::new();
+            for ws in filter.select(&cfg.workspaces) {
+                let repos = sync::resolve_repos(ws, refresh, &api_limiter).await?;
+                let mut present = 0;
+                for repo in &repos {
+                    if repos.path(ws, repo)?.exists() {
+                        present += 1;
+                    }
+                }
+                // Public/private base dirs hold repos of their own; size them all
+                let roots: Vec<_> = ws.resolved_base_dirs()?.iter().map(|dir| ws.repos_root(dir)).collect();
+                let bytes = roots.iter().map(|root| sync::dir_size(root)).sum();
+                let paths: Vec<String> = roots.iter().map(|root| root.display().to_string()).collect();
+                rows.push((ws.name.clone(), paths.join(", "), present, bytes));
+            }
+            display::print_disk_usage(&mut rows);
+        }
+
+        Commands::Discover {
+            org,
+            provider,
+            kind,
+            as_config,
+            output,
+            json,
+        } => {
+            let repos: Vec<String> = provider::discover_repos(&provider, None, &org, kind, &api_limiter)
+                .await?
+                .into_iter()
+                .map(|r| r.name)
+                .collect();
+            if json {
+                let out = serde_json::json!({ "org": org, "repos": repos, "count": repos.len() });
+                println!("{out}");
+            } else if !as_config {
+                display::print_discover_results(&org, &repos);
+            } else {
+                let content = config::generate_discovered_config(&provider, &org, &repos);
+                match output {
+                    Some(path) => {
+                        std::fs::write(&path, &content)
+                            .with_context(|| format!("writing {}", path.display()))?;
+                        display::outln!("config written to {}", path.display());
+                    }
+                    None => print!("{content}"),
+                }
+            }
+            if display::verbosity() >= display::Verbosity::Normal {
+                display::print_api_usage(&provider::api_usage());
+            }
+        }
+
+        #[cfg(feature = "tui")]
+        Commands::Tui {
+            config: config_path,
+            filter,
+            refresh,
+        } => {
+            let cfg = load_config(config_path.as_deref(), !cli.no_default_excludes)?;
+            let workspaces = filter.select(&cfg.workspaces);
+            tui::run(&workspaces, refresh, &api_limiter).await?;
+        }
+
+        Commands::Whoami => {
+            let info = provider::token_info().await?;
+            display::print_token_info(&info);
+        }
+
+        Commands::Ratelimit => {
+            let limits = provider::rate_limit().await?;
+            display::print_rate_limits(&limits);
+        }
+
+        Commands::Schema => {
+            println!("{}", config::Config::json_schema()?);
+        }
+
+        Commands::Config { action } => match action {
+            ConfigAction::Path { config: config_path } => {
+                let path = resolve_config_path(config_path.as_deref())?;
+                let state = if path.exists() { "exists" } else { "not found" };
+                println!("{} ({state})", path.display());
+            }
+            ConfigAction::Show { config: config_path } => {
+                let path = resolve_config_path(config_path.as_deref())?;
+                let cfg = load_config(config_path.as_deref(), true)?;
+                print!("{}", config::ConfigFormat::from_path(&path).to_string(&cfg)?);
+            }
+        },
+
+        Commands::Clone {
+            url,
+            dir,
+            ssh,
+            depth,
+            submodules,
+        } => {
+            let opts = sync::AdhocClone { ssh, depth, submodules };
+            let dest = sync::clone_adhoc(&url, dir.as_deref(), &opts)?;
+            display::outln!("cloned {url} into {}", dest.display());
+        }
+
+        Commands::Open {
+            repo,
             config: config_path,
             workspace: ws_filter,
+            web,
             refresh,
         } => {
-            let cfg = load_config(config_path.as_deref())?;
-            for ws in filter_workspaces(&cfg.workspaces, ws_filter.as_deref()) {
-                let repos = sync::resolve_repos(ws, refresh).await?;
-                display::print_repo_list(&ws.name, &repos);
+            let cfg = load_config(config_path.as_deref(), !cli.no_default_excludes)?;
+            let mut url = None;
+            for ws in filter_workspaces(&cfg.workspaces, ws_filter.as_deref(), &[], &[], false) {
+                let repos = sync::resolve_repos(ws, refresh, &api_limiter).await?;
+                if repos.contains(&repo) {
+                    url = Some(ws.web_url(&repo));
+                    break;
+                }
+            }
+            let url = match url {
+                Some(u) => u,
+                None => match ws_filter {
+                    Some(name) => anyhow::bail!("repo {repo} not found in workspace {name}"),
+                    None => anyhow::bail!("repo {repo} not found in any workspace"),
+                },
+            };
+            if web {
+                open::that(&url).with_context(|| format!("opening {url}"))?;
+            } else {
+                println!("{url}");
             }
         }
 
-        Commands::Discover { org, provider: _ } => {
-            let repos = provider::discover_github_repos(&org).await?;
-            display::print_discover_results(&org, &repos);
+        Commands::FlakeStatus {
+            config: config_path,
+            workspace: ws_filter,
+        } => {
+            let cfg = load_config(config_path.as_deref(), !cli.no_default_excludes)?;
+            for ws in filter_workspaces(&cfg.workspaces, ws_filter.as_deref(), &[], &[], false) {
+                if ws.flake_deps.is_empty() {
+                    continue;
+                }
+                let repos = sync::resolve_repos(ws, false, &api_limiter).await?;
+                display::print_flake_status(&ws.name, &flake::flake_status(ws, &repos)?);
+            }
         }
 
         Commands::FlakeUpdate {
@@ -227,30 +1211,89 @@ async fn main() -> Result<()> {
             config: config_path,
             workspace: ws_filter,
             dry_run,
-            quiet,
+            json,
+            sequential,
+            require_default_branch,
+            resume,
+            interactive,
+            commit_each,
+            commit_once,
+            rollback,
+            force,
+            inputs,
+            nix_arg,
         } => {
-            let cfg = load_config(config_path.as_deref())?;
-            for ws in filter_workspaces(&cfg.workspaces, ws_filter.as_deref()) {
+            let cfg = load_config(config_path.as_deref(), !cli.no_default_excludes)?;
+            if rollback {
+                for ws in filter_workspaces(&cfg.workspaces, ws_filter.as_deref(), &[], &[], false) {
+                    let Some(journal) = flake_journal::load(&ws.name)? else {
+                        display::outln!("{}: no flake-update chain recorded", ws.name);
+                        continue;
+                    };
+                    let Some(_lock) = lock::WorkspaceLock::try_acquire(&ws.name)? else {
+                        display::print_workspace_locked(&ws.name);
+                        continue;
+                    };
+                    let repos = sync::resolve_repos(ws, false, &api_limiter).await?;
+                    display::print_flake_rollback_header(&ws.name, &journal);
+                    flake::rollback_chain(ws, &repos, &journal, force)?;
+                    if force && !runner::is_dry_run() {
+                        flake_journal::clear(&ws.name)?;
+                    }
+                }
+                if !force {
+                    display::outln!("\n(re-run with --force to execute)");
+                }
+                return Ok(());
+            }
+            let changed = changed.context("--changed is required")?;
+            let opts = flake::ExecuteOpts {
+                dry_run,
+                quiet,
+                sequential,
+                require_default_branch,
+                resume,
+                interactive,
+                commit_granularity: match (commit_each, commit_once) {
+                    (true, _) => Some(config::FlakeCommitGranularity::PerInput),
+                    (_, true) => Some(config::FlakeCommitGranularity::PerRepo),
+                    _ => None,
+                },
+                nix_args: &nix_arg,
+                ..Default::default()
+            };
+            for ws in filter_workspaces(&cfg.workspaces, ws_filter.as_deref(), &[], &[], false) {
                 if ws.flake_deps.is_empty() {
                     continue;
                 }
+                let repos = sync::resolve_repos(ws, false, &api_limiter).await?;
+                if json {
+                    // Only the plan is printed, so no workspace lock is needed
+                    let chain = flake::compute_update_chain(&changed, &ws.flake_deps)?;
+                    let chain = flake::filter_chain_inputs(chain, &inputs);
+                    ensure_chain_inputs(ws, &repos, &chain)?;
+                    let plan = flake::UpdatePlan { workspace: &ws.name, changed: &changed, steps: &chain };
+                    println!("{}", serde_json::to_string(&plan)?);
+                    continue;
+                }
+                let Some(_lock) = lock::WorkspaceLock::try_acquire(&ws.name)? else {
+                    display::print_workspace_locked(&ws.name);
+                    continue;
+                };
                 let chain = flake::compute_update_chain(&changed, &ws.flake_deps)?;
                 if chain.is_empty() {
-                    if !quiet {
-                        println!(
-                            "{}: {} has no dependents in flake_deps",
-                            ws.name, changed
-                        );
-                    }
+                    display::outln!("{}: {} has no dependents in flake_deps", ws.name, changed);
                     continue;
                 }
-                if !quiet {
-                    display::print_flake_chain_header(&ws.name, &changed, &chain);
-                }
-                flake::execute_update_chain(ws, &chain, dry_run, quiet)?;
-                if !quiet {
-                    display::print_flake_chain_complete(chain.len());
+                let chain = flake::filter_chain_inputs(chain, &inputs);
+                if chain.is_empty() {
+                    display::outln!("{}: no step updates any of the --input inputs", ws.name);
+                    continue;
                 }
+                display::print_flake_chain_header(&ws.name, &changed, &chain);
+                ensure_chain_inputs(ws, &repos, &chain)?;
+                flake::execute_update_chain(ws, &repos, &changed, &chain, &opts)?;
+                display::print_flake_chain_complete(chain.len());
             }
         }
 
@@ -259,11 +1302,15 @@ async fn main() -> Result<()> {
             workspace: ws_filter,
             refresh: _refresh,
         } => {
-            let cfg = load_config(config_path.as_deref())?;
+            let cfg = load_config(config_path.as_deref(), !cli.no_default_excludes)?;
             let audit_log = audit::AuditLog::default_path();
-            for ws in filter_workspaces(&cfg.workspaces, ws_filter.as_deref()) {
+            for ws in filter_workspaces(&cfg.workspaces, ws_filter.as_deref(), &[], &[], false) {
                 if let Some(ref watch_cfg) = ws.watch {
                     if watch_cfg.enable {
+                        let Some(_lock) = lock::WorkspaceLock::try_acquire(&ws.name)? else {
+                            display::print_workspace_locked(&ws.name);
+                            continue;
+                        };
                         let gh = github::HttpGitHubClient::new()?;
                         let cache_store = watch_cache::FsWatchStateStore;
                         let matrix_appender = watch::TomlMatrixAppender;
@@ -271,7 +1318,7 @@ async fn main() -> Result<()> {
 
                         let summary = watch::run_watch_cycle(
                             ws, false, &gh, &cache_store, &matrix_appender, &git_ops,
-                            &audit_log,
+                            &audit_log, &api_limiter,
                         ).await?;
                         display::print_watch_summary(&ws.name, &summary);
                     }
@@ -359,11 +1406,15 @@ async fn main() -> Result<()> {
 
         Commands::Daemon {
             config: config_path,
-            workspace: ws_filter,
+            filter,
             interval,
             fetch,
-            quiet,
             github_token_file,
+            notify_webhook,
+            health_port,
+            health_bind,
+            maintenance,
+            maintenance_every,
         } => {
             // In launchd/systemd environments, env vars may not be inherited.
             // Read the token from a file and set GITHUB_TOKEN for provider discovery.
@@ -375,16 +1426,91 @@ async fn main() -> Result<()> {
 
             daemon::run(daemon::DaemonOpts {
                 config: config_path,
-                workspace: ws_filter,
+                filter,
                 interval,
                 fetch,
                 quiet,
+                api_jobs: cli.api_jobs.max(1),
+                notify_webhook,
+                health_addr: health_port.map(|port| std::net::SocketAddr::new(health_bind, port)),
+                maintenance_every: maintenance.then_some(maintenance_every.max(1)),
             })
             .await?;
         }
 
-        Commands::Init => {
-            let path = config::Config::default_path();
+        Commands::AddRepo {
+            workspace,
+            repo,
+            config: config_path,
+            sync,
+        } => {
+            let path = writable_config_path(config_path.as_deref())?;
+            let mut cfg = config::Config::read(&path)?;
+            if cfg.workspace_mut(&workspace)?.add_extra_repo(&repo) {
+                cfg.save(&path)?;
+                display::outln!("added {repo} to {workspace} extra_repos");
+            } else {
+                display::outln!("{repo} already in {workspace} extra_repos");
+            }
+
+            if sync {
+                let cfg = config::Config::load(&path)?;
+                let ws = cfg
+                    .workspaces
+                    .iter()
+                    .find(|ws| ws.name == workspace)
+                    .with_context(|| format!("no workspace named {workspace} in config"))?;
+                let Some(_lock) = lock::WorkspaceLock::try_acquire(&ws.name)? else {
+                    display::print_workspace_locked(&ws.name);
+                    return Ok(());
+                };
+                let outcome = sync::sync_repos(ws, &vec![repo].into(), sync::SyncOpts::default()).await?;
+                display::print_sync_summary(&ws.name, outcome.cloned, outcome.present);
+            }
+        }
+
+        Commands::ExcludeRepo {
+            workspace,
+            repo,
+            config: config_path,
+        } => {
+            let path = writable_config_path(config_path.as_deref())?;
+            let mut cfg = config::Config::read(&path)?;
+            if cfg.workspace_mut(&workspace)?.add_exclude(&repo) {
+                cfg.save(&path)?;
+                display::outln!("added {repo} to {workspace} exclude");
+            } else {
+                display::outln!("{repo} already in {workspace} exclude");
+            }
+        }
+
+        Commands::RenameRepo {
+            old,
+            new,
+            config: config_path,
+            dry_run,
+        } => {
+            let path = writable_config_path(config_path.as_deref())?;
+            let format = config::ConfigFormat::from_path(&path);
+            let contents = std::fs::read_to_string(&path)
+                .with_context(|| format!("reading {}", path.display()))?;
+            let mut doc: serde_yaml_ng::Value = format
+                .parse(&contents)
+                .with_context(|| format!("parsing {}", path.display()))?;
+
+            let edits = config::rename_repo(&mut doc, &old, &new);
+            display::print_rename_edits(&old, &new, &edits, dry_run);
+            if !dry_run && !edits.is_empty() {
+                let updated = format.to_string(&doc).context("serializing config")?;
+                // Refuse to write anything that no longer parses as a config
+                config::Config::parse(format, &updated).context("renamed config is invalid")?;
+                std::fs::write(&path, updated)
+                    .with_context(|| format!("writing {}", path.display()))?;
+            }
+        }
+
+        Commands::Init { format } => {
+            let path = config::Config::default_path().with_extension(format.extension());
             if path.exists() {
                 anyhow::bail!("config already exists at {}", path.display());
             }
@@ -392,30 +1518,348 @@ async fn main() -> Result<()> {
                 std::fs::create_dir_all(parent)
                     .with_context(|| format!("creating {}", parent.display()))?;
             }
-            let content = config::generate_starter_config();
+            let content = config::generate_starter_config(format);
             std::fs::write(&path, &content)
                 .with_context(|| format!("writing {}", path.display()))?;
-            println!("config written to {}", path.display());
+            display::outln!("config written to {}", path.display());
+            if format != config::ConfigFormat::Yaml {
+                // Discovery only looks for YAML names; point tend at the file explicitly
+                display::outln!("set TEND_CONFIG={} to use it by default", path.display());
+            }
         }
     }
 
     Ok(())
 }
 
-pub(crate) fn load_config(path: Option<&std::path::Path>) -> Result<config::Config> {
-    let config_path = match path {
-        Some(p) => p.to_path_buf(),
-        None => config::Config::default_path(),
+/// The config file: `--config`, else `$TEND_CONFIG` (which must be a file), else
+/// the discovered default.
+fn resolve_config_path(path: Option<&std::path::Path>) -> Result<PathBuf> {
+    match path {
+        Some(p) => Ok(p.to_path_buf()),
+        None => Ok(config::Config::env_path()?.unwrap_or_else(config::Config::default_path)),
+    }
+}
+
+/// `resolve_config_path` for commands that write the config back, which
+/// can't when it was read from stdin (`--config -`).
+fn writable_config_path(path: Option<&std::path::Path>) -> Result<PathBuf> {
+    if path == Some(std::path::Path::new("-")) {
+        return Err(exit::Failure::Config.tag(anyhow::anyhow!(
+            "--config - reads the config from stdin, so this command has nowhere to write it back"
+        )));
+    }
+    resolve_config_path(path)
+}
+
+/// Load the config (from stdin for `--config -`); `global_excludes` false
+/// drops `global_exclude` for this run.
+pub(crate) fn load_config(path: Option<&std::path::Path>, global_excludes: bool) -> Result<config::Config> {
+    let cfg = match path {
+        Some(p) if p == std::path::Path::new("-") => config::Config::load_stdin()?,
+        _ => config::Config::load(&resolve_config_path(path)?)?,
     };
-    config::Config::load(&config_path)
+    Ok(if global_excludes { cfg } else { cfg.without_global_excludes() })
 }
 
-pub(crate) fn filter_workspaces<'a>(
+/// Fail with a config error when `flake_deps` names inputs the chain's flakes don't declare.
+fn ensure_chain_inputs(ws: &config::Workspace, repos: &sync::ResolvedRepos, chain: &[flake::UpdateStep]) -> Result<()> {
+    let missing = flake::check_chain_inputs(ws, repos, chain)?;
+    if missing.is_empty() {
+        return Ok(());
+    }
+    display::print_flake_missing_inputs(&ws.name, &missing);
+    Err(exit::Failure::Config.tag(anyhow::anyhow!(
+        "{} flake_deps input(s) in workspace {} not declared by their flake; fix the config and re-run",
+        missing.len(),
+        ws.name
+    )))
+}
+
+/// Run `task` for each workspace on its own tokio task, at most `limit` at a time.
+/// Results come back in workspace order so callers can print them one workspace
+/// at a time; an error (or panic) in one workspace doesn't stop the others.
+/// What a workspace prints while it runs is held and printed in one piece as
+/// soon as it finishes, so concurrent workspaces don't interleave their lines.
+async fn run_workspaces_parallel<T, F, Fut>(
+    workspaces: &[config::Workspace],
+    limit: usize,
+    task: F,
+) -> Vec<(String, Result<T>)>
+where
+    T: Send + 'static,
+    F: Fn(config::Workspace) -> Fut,
+    Fut: Future<Output = Result<T>> + Send + 'static,
+{
+    let permits = Arc::new(tokio::sync::Semaphore::new(limit.max(1)));
+    let mut tasks = tokio::task::JoinSet::new();
+    for (i, ws) in workspaces.iter().enumerate() {
+        let permits = Arc::clone(&permits);
+        let fut = task(ws.clone());
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            let (result, output) = display::buffered(fut).await;
+            (i, result, output)
+        });
+    }
+
+    let mut results: Vec<Option<Result<T>>> = workspaces.iter().map(|_| None).collect();
+    while let Some(joined) = tasks.join_next().await {
+        if let Ok((i, result, output)) = joined {
+            output.flush();
+            results[i] = Some(result);
+        }
+    }
+    workspaces
+        .iter()
+        .zip(results)
+        .map(|(ws, result)| {
+            let result = result.unwrap_or_else(|| Err(anyhow::anyhow!("workspace task panicked")));
+            (ws.name.clone(), result)
+        })
+        .collect()
+}
+
+/// Select workspaces by name and tag. An explicit `filter` wins over `exclude`;
+/// otherwise every workspace not named in `exclude` is selected. `tags` then
+/// narrows the selection to workspaces carrying any of them (all with `all_tags`).
+fn filter_workspaces<'a>(
+    workspaces: &'a [config::Workspace],
+    filter: Option<&str>,
+    exclude: &[String],
+    tags: &[String],
+    all_tags: bool,
+) -> Vec<&'a config::Workspace> {
+    let mut selected = select_by_name(workspaces, filter, exclude);
+    selected.retain(|ws| ws.matches_tags(tags, all_tags));
+    selected
+}
+
+/// Point the one workspace `--workspace` selects at `--base-dir`, with `~` and
+/// `$VAR` expanded. Its public/private base dirs are dropped so every repo lands
+/// there. Several matching workspaces would share the dir, so that's an error.
+fn override_base_dir(workspaces: &mut [config::Workspace], filter: Option<&str>, base_dir: &str) -> Result<()> {
+    let selected: Vec<String> = select_by_name(workspaces, filter, &[])
+        .iter()
+        .map(|ws| ws.name.clone())
+        .collect();
+    let [name] = selected.as_slice() else {
+        anyhow::bail!(
+            "--base-dir needs --workspace to select exactly one workspace ({} matched)",
+            selected.len()
+        );
+    };
+    let expanded = shellexpand::full(base_dir).with_context(|| format!("expanding --base-dir {base_dir}"))?;
+    if let Some(ws) = workspaces.iter_mut().find(|ws| &ws.name == name) {
+        ws.base_dir = expanded.into_owned();
+        ws.public_base_dir = None;
+        ws.private_base_dir = None;
+    }
+    Ok(())
+}
+
+fn select_by_name<'a>(
     workspaces: &'a [config::Workspace],
     filter: Option<&str>,
+    exclude: &[String],
 ) -> Vec<&'a config::Workspace> {
     match filter {
+        // Glob patterns select a family of workspaces; excludes still apply
+        Some(pattern) if pattern.contains(['*', '?', '[']) => {
+            match globset::Glob::new(pattern) {
+                Ok(glob) => {
+                    let matcher = glob.compile_matcher();
+                    workspaces
+                        .iter()
+                        .filter(|ws| matcher.is_match(&ws.name) && !exclude.contains(&ws.name))
+                        .collect()
+                }
+                // Not a valid glob: fall back to an exact name match
+                Err(_) => workspaces.iter().filter(|ws| ws.name == pattern).collect(),
+            }
+        }
         Some(name) => workspaces.iter().filter(|ws| ws.name == name).collect(),
-        None => workspaces.iter().collect(),
+        None => workspaces
+            .iter()
+            .filter(|ws| !exclude.contains(&ws.name))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_workspaces(names: &[&str]) -> Vec<config::Workspace> {
+        names
+            .iter()
+            .map(|name| config::Workspace {
+                name: name.to_string(),
+                base_dir: format!("/tmp/{name}"),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn names(selected: &[&config::Workspace]) -> Vec<String> {
+        selected.iter().map(|ws| ws.name.clone()).collect()
+    }
+
+    #[test]
+    fn test_override_base_dir_needs_exactly_one_workspace() {
+        let mut workspaces = make_workspaces(&["team-a", "team-b"]);
+        workspaces[0].private_base_dir = Some("/srv/private".to_string());
+        assert!(override_base_dir(&mut workspaces, Some("team-*"), "/tmp/ci").is_err());
+        assert!(override_base_dir(&mut workspaces, Some("nope"), "/tmp/ci").is_err());
+
+        override_base_dir(&mut workspaces, Some("team-a"), "~/ci").unwrap();
+        assert!(!workspaces[0].base_dir.starts_with('~'));
+        assert!(workspaces[0].base_dir.ends_with("/ci"));
+        assert_eq!(workspaces[0].private_base_dir, None);
+        assert_eq!(workspaces[1].base_dir, "/tmp/team-b");
+
+        assert!(Cli::try_parse_from(["tend", "sync", "--base-dir", "/tmp/ci"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_workspaces_parallel_keeps_order_and_isolates_errors() {
+        let workspaces = make_workspaces(&["slow", "broken", "fast"]);
+        let results = run_workspaces_parallel(&workspaces, 2, |ws| async move {
+            match ws.name.as_str() {
+                "slow" => tokio::time::sleep(std::time::Duration::from_millis(50)).await,
+                "broken" => anyhow::bail!("boom"),
+                _ => {}
+            }
+            Ok(ws.base_dir)
+        })
+        .await;
+
+        let names: Vec<&str> = results.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["slow", "broken", "fast"]);
+        assert_eq!(results[0].1.as_ref().unwrap(), "/tmp/slow");
+        assert!(results[1].1.is_err());
+        assert_eq!(results[2].1.as_ref().unwrap(), "/tmp/fast");
+    }
+
+    #[test]
+    fn test_filter_workspaces_glob() {
+        let workspaces = make_workspaces(&["team-a-public", "team-a-private", "team-b"]);
+        let exclude = vec!["team-a-private".to_string()];
+        assert_eq!(
+            names(&filter_workspaces(&workspaces, Some("team-a-*"), &[], &[], false)),
+            vec!["team-a-public", "team-a-private"]
+        );
+        assert_eq!(
+            names(&filter_workspaces(&workspaces, Some("team-a-*"), &exclude, &[], false)),
+            vec!["team-a-public"]
+        );
+        assert!(filter_workspaces(&workspaces, Some("team-a"), &[], &[], false).is_empty());
+        assert!(filter_workspaces(&workspaces, Some("team-[a"), &[], &[], false).is_empty());
+    }
+
+    #[test]
+    fn test_filter_workspaces_exclude() {
+        let workspaces = make_workspaces(&["a", "b", "c"]);
+        let selected = filter_workspaces(&workspaces, None, &["b".to_string()], &[], false);
+        assert_eq!(names(&selected), vec!["a", "c"]);
+    }
+
+    #[test]
+    fn test_filter_workspaces_include_wins_over_exclude() {
+        let workspaces = make_workspaces(&["a", "b", "c"]);
+        let selected = filter_workspaces(&workspaces, Some("b"), &["b".to_string()], &[], false);
+        assert_eq!(names(&selected), vec!["b"]);
+    }
+
+    #[test]
+    fn test_filter_workspaces_tags() {
+        let mut workspaces = make_workspaces(&["a", "b", "c"]);
+        workspaces[0].tags = vec!["work".to_string(), "oss".to_string()];
+        workspaces[1].tags = vec!["work".to_string()];
+        workspaces[2].tags = vec!["personal".to_string()];
+        let tags = |t: &[&str]| t.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        let selected = filter_workspaces(&workspaces, None, &[], &tags(&["work"]), false);
+        assert_eq!(names(&selected), vec!["a", "b"]);
+        let selected = filter_workspaces(&workspaces, None, &[], &tags(&["oss", "personal"]), false);
+        assert_eq!(names(&selected), vec!["a", "c"]);
+        let selected = filter_workspaces(&workspaces, None, &[], &tags(&["work", "oss"]), true);
+        assert_eq!(names(&selected), vec!["a"]);
+        // Composes with --workspace and --exclude-workspace
+        let selected = filter_workspaces(&workspaces, Some("b"), &[], &tags(&["work"]), false);
+        assert_eq!(names(&selected), vec!["b"]);
+        let selected = filter_workspaces(&workspaces, None, &tags(&["a"]), &tags(&["work"]), false);
+        assert_eq!(names(&selected), vec!["b"]);
+    }
+
+    #[test]
+    fn test_workspace_filter_flags_are_shared() {
+        let cli = Cli::try_parse_from(["tend", "du", "--exclude-workspace", "b", "--tag", "work"]).unwrap();
+        let Commands::Du { filter, .. } = cli.command else { panic!("parsed as another command") };
+        let workspaces = ["a", "b", "c"].map(|name| config::Workspace {
+            name: name.to_string(),
+            tags: vec!["work".to_string()],
+            ..Default::default()
+        });
+        let names: Vec<&str> = filter.select(&workspaces).iter().map(|ws| ws.name.as_str()).collect();
+        assert_eq!(names, ["a", "c"]);
+
+        let cli = Cli::try_parse_from(["tend", "list", "--workspace", "a", "--base-dir", "/tmp/ci"]).unwrap();
+        let Commands::List { filter, base_dir, .. } = cli.command else { panic!("parsed as another command") };
+        let mut workspaces = workspaces.to_vec();
+        base_dir.apply(&mut workspaces, &filter).unwrap();
+        assert_eq!(workspaces[0].base_dir, "/tmp/ci");
+        assert!(Cli::try_parse_from(["tend", "status", "--base-dir", "/tmp/ci"]).is_err());
+    }
+
+    #[test]
+    fn test_config_writers_reject_stdin_config() {
+        let err = writable_config_path(Some(std::path::Path::new("-"))).unwrap_err();
+        assert!(err.to_string().contains("stdin"), "{err}");
+        assert_eq!(exit::exit_code(&err), 2);
+        let path = std::path::Path::new("/tmp/tend.yaml");
+        assert_eq!(writable_config_path(Some(path)).unwrap(), path);
+    }
+
+    #[test]
+    fn test_verbosity_flags_are_global() {
+        let cli = Cli::try_parse_from(["tend", "sync", "--quiet"]).unwrap();
+        assert!(cli.quiet && !cli.silent);
+        let cli = Cli::try_parse_from(["tend", "--silent", "status"]).unwrap();
+        assert!(cli.silent);
+        assert!(Cli::try_parse_from(["tend", "fetch", "--quiet", "--silent"]).is_err());
+        assert!(Cli::try_parse_from(["tend", "sync", "-v"]).unwrap().verbose);
+        assert!(Cli::try_parse_from(["tend", "status", "-v", "--quiet"]).is_err());
+    }
+
+    #[test]
+    fn test_timestamps_from_flag_or_env() {
+        assert!(Cli::try_parse_from(["tend", "sync", "--timestamps"]).unwrap().timestamps);
+        assert!(display::timestamps_from_env(Some("1")));
+        assert!(display::timestamps_from_env(Some("True")));
+        assert!(!display::timestamps_from_env(Some("0")));
+        assert!(!display::timestamps_from_env(None));
+    }
+
+    #[test]
+    fn test_discover_json_conflicts_with_as_config() {
+        assert!(Cli::try_parse_from(["tend", "discover", "acme", "--json"]).is_ok());
+        assert!(Cli::try_parse_from(["tend", "discover", "acme", "--json", "--as-config"]).is_err());
+    }
+
+    #[test]
+    fn test_flake_update_json_requires_dry_run() {
+        assert!(Cli::try_parse_from(["tend", "flake-update", "--changed", "a", "--json"]).is_err());
+        assert!(Cli::try_parse_from(["tend", "flake-update", "--changed", "a", "--dry-run", "--json"]).is_ok());
+    }
+
+    #[test]
+    fn test_global_dry_run_defers_to_command_dry_run() {
+        assert!(Cli::try_parse_from(["tend", "sync", "--dry-run"]).unwrap().dry_run);
+        assert!(Cli::try_parse_from(["tend", "--dry-run", "fetch"]).unwrap().dry_run);
+
+        // Commands with their own --dry-run take it either side of the subcommand
+        let cli = Cli::try_parse_from(["tend", "--dry-run", "flake-update", "--changed", "a"]).unwrap();
+        assert!(matches!(cli.command, Commands::FlakeUpdate { dry_run: true, .. }));
     }
 }
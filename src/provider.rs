@@ -1,64 +1,866 @@
 use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::Semaphore;
 
-use crate::cache;
+use crate::cache::{self, ListingPage};
+use crate::config::OwnerKind;
+use crate::display;
+use crate::exit::Failure;
 
-/// Cached wrapper around `discover_github_repos`.
-/// Returns cached results if fresh (within TTL); otherwise hits the API and writes cache.
+/// Default number of concurrent GitHub API requests (`--api-jobs`).
+pub const DEFAULT_API_JOBS: usize = 4;
+
+/// A repo found by discovery. Only the name and last-push time are kept.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiscoveredRepo {
+    pub name: String,
+    /// ISO-8601 time of the last push (`pushed_at` on GitHub, `updated` on Sourcehut)
+    #[serde(default)]
+    pub pushed_at: Option<String>,
+    /// `private` on GitHub, `visibility` on Sourcehut; `None` in caches written before it was kept
+    #[serde(default)]
+    pub private: Option<bool>,
+}
+
+/// Cached discovery for a workspace provider (`github` or `sourcehut`).
+/// Returns cached results if written within `ttl_secs`; otherwise hits the API and writes cache.
 /// Pass `refresh = true` to bypass the cache and always hit the API.
-pub async fn discover_github_repos_cached(org: &str, refresh: bool) -> Result<Vec<String>> {
+///
+/// Past the TTL (or with `refresh`), GitHub listings are revalidated with the
+/// cached per-page ETags: pages answered `304 Not Modified` are reused as-is.
+pub async fn discover_repos_cached(
+    provider: &str,
+    api_base: Option<&str>,
+    owner: &str,
+    kind: OwnerKind,
+    refresh: bool,
+    ttl_secs: u64,
+    limiter: &Semaphore,
+) -> Result<Vec<DiscoveredRepo>> {
+    let owner = owner.trim_start_matches('~');
+    // Enterprise orgs get their own cache namespace per API host
+    let cache_provider = match api_base {
+        Some(base) => format!("{provider}-{}", cache_safe(base)),
+        None => provider.to_string(),
+    };
     if !refresh {
-        if let Some(repos) = cache::read(org) {
+        if let Some(repos) = cache::read(&cache_provider, owner, ttl_secs) {
             return Ok(repos);
         }
     }
 
-    let repos = discover_github_repos(org).await?;
-    let _ = cache::write(org, &repos); // best-effort cache write
+    let cached_pages = cache::read_pages(&cache_provider, owner);
+    let (repos, pages) = discover_listing(provider, api_base, owner, kind, &cached_pages, limiter).await?;
+    let _ = cache::write(&cache_provider, owner, &repos, &pages); // best-effort cache write
     Ok(repos)
 }
 
+/// Cached `search_repos`, keyed by owner and query, with the same TTL and
+/// `refresh` behavior as `discover_repos_cached`. Search responses carry no
+/// per-page ETags, so an expired entry is simply re-fetched.
+pub async fn search_repos_cached(
+    api_base: Option<&str>,
+    owner: &str,
+    query: &str,
+    refresh: bool,
+    ttl_secs: u64,
+    limiter: &Semaphore,
+) -> Result<Vec<DiscoveredRepo>> {
+    let cache_provider = match api_base {
+        Some(base) => format!("github-search-{}", cache_safe(base)),
+        None => "github-search".to_string(),
+    };
+    let key = search_cache_key(owner, query);
+    if !refresh {
+        if let Some(repos) = cache::read(&cache_provider, &key, ttl_secs) {
+            return Ok(repos);
+        }
+    }
+
+    let repos = search_repos(api_base.unwrap_or(GITHUB_API), owner, query, limiter).await?;
+    let _ = cache::write(&cache_provider, &key, &repos, &[]); // best-effort cache write
+    Ok(repos)
+}
+
+/// Cache file name for a search: the owner plus the query with every byte
+/// outside `[A-Za-z0-9.-]` hex-escaped, so `stars:>10` and `stars:<10` differ.
+fn search_cache_key(owner: &str, query: &str) -> String {
+    let mut key = format!("{owner}_");
+    for b in query.bytes() {
+        if b.is_ascii_alphanumeric() || b == b'.' || b == b'-' {
+            key.push(b as char);
+        } else {
+            key.push_str(&format!("_{b:02x}"));
+        }
+    }
+    key
+}
+
+/// Reduce a URL to characters safe for a cache directory name.
+fn cache_safe(url: &str) -> String {
+    url.trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' { c } else { '_' })
+        .collect()
+}
+
+/// Discover repos for `owner` using the named provider.
+/// `api_base` overrides the GitHub API root (GitHub Enterprise) and `kind`
+/// picks the GitHub endpoint; both are ignored for Sourcehut.
+pub async fn discover_repos(
+    provider: &str,
+    api_base: Option<&str>,
+    owner: &str,
+    kind: OwnerKind,
+    limiter: &Semaphore,
+) -> Result<Vec<DiscoveredRepo>> {
+    Ok(discover_listing(provider, api_base, owner, kind, &[], limiter).await?.0)
+}
+
+/// `discover_repos`, revalidating `cached` GitHub pages. Also returns the
+/// pages to cache for next time (always empty for Sourcehut).
+async fn discover_listing(
+    provider: &str,
+    api_base: Option<&str>,
+    owner: &str,
+    kind: OwnerKind,
+    cached: &[ListingPage],
+    limiter: &Semaphore,
+) -> Result<(Vec<DiscoveredRepo>, Vec<ListingPage>)> {
+    match provider {
+        "github" => discover_github_listing(api_base.unwrap_or(GITHUB_API), owner, kind, cached, limiter).await,
+        "sourcehut" => Ok((discover_sourcehut_repos(owner, srht_token().as_deref(), limiter).await?, Vec::new())),
+        other => anyhow::bail!("unsupported provider '{other}' (expected github or sourcehut)"),
+    }
+}
+
 /// Discover all repos in a GitHub org or user account via REST API.
-/// Tries the /orgs endpoint first; falls back to /users on 404.
+/// With `OwnerKind::Auto`, tries the /orgs endpoint first and falls back to /users
+/// on 404; `Org` and `User` query only their endpoint.
 /// Uses TEND_GITHUB_TOKEN or GITHUB_TOKEN env var for auth (optional but needed for private repos).
-pub async fn discover_github_repos(org: &str) -> Result<Vec<String>> {
-    use todoku::{GitHubApi, OwnerType};
+///
+/// Requests go through `api_client`, so proxy and `TEND_CA_BUNDLE` settings apply.
+/// Each API request holds a permit from `limiter`, bounding the total number of
+/// outstanding requests when several workspaces resolve concurrently.
+///
+/// `cached` pages are revalidated with `If-None-Match` (see `list_github_repos`).
+async fn discover_github_listing(
+    api_base: &str,
+    org: &str,
+    kind: OwnerKind,
+    cached: &[ListingPage],
+    limiter: &Semaphore,
+) -> Result<(Vec<DiscoveredRepo>, Vec<ListingPage>)> {
+    let client = api_client()?;
+    let token = github_token().filter(|t| !t.trim().is_empty());
+    let token = token.as_deref();
+    let client = &client;
+    let list = |url: String| async move { list_github_repos(client, token, &url, cached, limiter).await };
+
+    let users_url = repos_url(api_base, "users", org);
+    let (mut repos, pages) = match kind {
+        OwnerKind::Org => list(repos_url(api_base, "orgs", org))
+            .await?
+            .with_context(|| format!("no GitHub org named {org}"))?,
+        OwnerKind::User => list(users_url)
+            .await?
+            .with_context(|| format!("no GitHub user named {org}"))?,
+        // Try org endpoint first, then user endpoint on 404
+        OwnerKind::Auto => match list(repos_url(api_base, "orgs", org)).await? {
+            Some(listing) => listing,
+            None => list(users_url)
+                .await?
+                .with_context(|| format!("no GitHub org or user named {org}"))?,
+        },
+    };
+    repos.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok((repos, pages))
+}
+
+/// GitHub returns at most this many results for one search, however it is paged.
+const SEARCH_RESULT_CAP: usize = 1000;
+
+/// Rate-limited GitHub requests are retried this many times before failing.
+const RATE_LIMIT_RETRIES: u32 = 3;
+
+/// Longest wait for a GitHub rate limit to reset before giving up instead.
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+/// Resolve repos with GitHub's `/search/repositories` endpoint.
+///
+/// Only repos owned by `owner` are kept, because clone URLs are built from the
+/// workspace's org; archived repos are skipped as in org discovery. The search
+/// API has its own, much lower rate limit (30 requests a minute with a token),
+/// so a rate-limited page waits for the reset and is retried (see `send_github`).
+async fn search_repos(api_base: &str, owner: &str, query: &str, limiter: &Semaphore) -> Result<Vec<DiscoveredRepo>> {
+    const PER_PAGE: usize = 100;
+    let client = api_client()?;
+    let token = github_token().filter(|t| !t.trim().is_empty());
+    let url = format!("{}/search/repositories", api_base.trim_end_matches('/'));
+
+    let mut repos = Vec::new();
+    for page in 1..=SEARCH_RESULT_CAP / PER_PAGE {
+        let body = search_page(&client, token.as_deref(), &url, query, page, PER_PAGE, limiter).await?;
+        let (page_repos, count) = parse_search_page(&body, owner);
+        repos.extend(page_repos);
+        if count < PER_PAGE {
+            break;
+        }
+    }
+    repos.sort_by(|a, b| a.name.cmp(&b.name));
+    repos.dedup_by(|a, b| a.name == b.name);
+    Ok(repos)
+}
+
+/// Fetch one page of search results.
+async fn search_page(
+    client: &reqwest::Client,
+    token: Option<&str>,
+    url: &str,
+    query: &str,
+    page: usize,
+    per_page: usize,
+    limiter: &Semaphore,
+) -> Result<serde_json::Value> {
+    let mut req = client
+        .get(url)
+        .query(&[("q", query.to_string()), ("per_page", per_page.to_string()), ("page", page.to_string())])
+        .header("Accept", "application/vnd.github+json");
+    if let Some(token) = token {
+        req = req.bearer_auth(token.trim());
+    }
+    let resp = send_github(req, url, limiter).await?;
+    let status = resp.status();
+    if !status.is_success() {
+        return Err(Failure::Network.tag(anyhow::anyhow!("GET {url} returned {status}")));
+    }
+    resp.json().await.context("parsing search results")
+}
+
+/// Send a GitHub API request, holding a `limiter` permit while it is in flight.
+/// A rate-limited response waits for the limit to reset (up to
+/// `MAX_RATE_LIMIT_WAIT`) and is retried, at most `RATE_LIMIT_RETRIES` times.
+/// Any other response, successful or not, is returned as is.
+async fn send_github(req: reqwest::RequestBuilder, url: &str, limiter: &Semaphore) -> Result<reqwest::Response> {
+    let mut retries = 0;
+    loop {
+        let attempt = req.try_clone().context("cloning API request")?;
+        let permit = limiter.acquire().await.context("API limiter closed")?;
+        let resp = send_counted(attempt, url).await?;
+        drop(permit);
+
+        let now = chrono::Utc::now().timestamp().max(0) as u64;
+        let Some(wait) = rate_limit_wait(resp.status(), resp.headers(), now) else {
+            return Ok(resp);
+        };
+        if retries == RATE_LIMIT_RETRIES || wait > MAX_RATE_LIMIT_WAIT {
+            return Err(Failure::Network.tag(anyhow::anyhow!(
+                "GitHub rate limit exceeded for {url}; it resets in {}s",
+                wait.as_secs()
+            )));
+        }
+        retries += 1;
+        crate::display::errln!("GitHub rate limit reached, retrying in {}s", wait.as_secs());
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// Send one GitHub API request and count it in the usage summary. Every
+/// GitHub request tend makes itself goes through here.
+async fn send_counted(req: reqwest::RequestBuilder, url: &str) -> Result<reqwest::Response> {
+    let resp = req.send().await.with_context(|| format!("requesting {url}"))?;
+    API_COUNTER.record(resp.headers());
+    Ok(resp)
+}
+
+/// Count a GitHub API request made through todoku, whose responses tend
+/// doesn't see, so it carries no rate limit headers.
+pub(crate) fn count_api_request() {
+    API_COUNTER.record(&reqwest::header::HeaderMap::new());
+}
+
+/// How long to wait before retrying a rate-limited response: `Retry-After`,
+/// else until `X-RateLimit-Reset` once `X-RateLimit-Remaining` hits 0.
+/// `None` when the response is not a rate limit (e.g. a plain 403).
+fn rate_limit_wait(status: reqwest::StatusCode, headers: &reqwest::header::HeaderMap, now: u64) -> Option<Duration> {
+    if status != reqwest::StatusCode::FORBIDDEN && status != reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return None;
+    }
+    let header = |name: &str| -> Option<u64> { headers.get(name)?.to_str().ok()?.trim().parse().ok() };
+    if let Some(secs) = header("retry-after") {
+        return Some(Duration::from_secs(secs));
+    }
+    match (header("x-ratelimit-remaining"), header("x-ratelimit-reset")) {
+        // One extra second so the retry lands after the reset, not on it
+        (Some(0), Some(reset)) => Some(Duration::from_secs(reset.saturating_sub(now) + 1)),
+        _ => None,
+    }
+}
 
-    let token = github_token();
-    let client = todoku::GitHubClient::new(token.as_deref())
-        .map_err(|e| anyhow::anyhow!("{e}"))
-        .context("building GitHub client")?;
+/// GitHub API requests made during this command, for the usage summary.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ApiUsage {
+    pub requests: usize,
+    /// Lowest core `X-RateLimit-Remaining` any response reported
+    pub remaining: Option<u64>,
+}
 
-    // Try org endpoint first, then user endpoint on 404
-    match client.list_repos(org, OwnerType::Org).await {
-        Ok(repos) => {
-            let mut names: Vec<String> = repos
-                .into_iter()
-                .filter(|r| !r.archived)
-                .map(|r| r.name)
-                .collect();
-            names.sort();
-            return Ok(names);
+/// Counts GitHub API responses. Concurrent pages can arrive out of order, so
+/// the lowest remaining count seen is kept rather than the last one.
+struct ApiCounter {
+    requests: AtomicUsize,
+    /// `u64::MAX` until a response carries the header
+    remaining: AtomicU64,
+}
+
+impl ApiCounter {
+    const fn new() -> Self {
+        Self { requests: AtomicUsize::new(0), remaining: AtomicU64::new(u64::MAX) }
+    }
+
+    /// Count one response. Search responses report the separate search bucket
+    /// (`X-RateLimit-Resource: search`), so only core ones update `remaining`.
+    fn record(&self, headers: &reqwest::header::HeaderMap) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).map(str::trim);
+        if header("x-ratelimit-resource").is_some_and(|r| r != "core") {
+            return;
         }
-        Err(todoku::TodokuError::Http { status: 404, .. }) => {
-            // org endpoint returned 404, try user endpoint
+        if let Some(remaining) = header("x-ratelimit-remaining").and_then(|r| r.parse().ok()) {
+            self.remaining.fetch_min(remaining, Ordering::Relaxed);
         }
-        Err(e) => return Err(anyhow::anyhow!("{e}").context("fetching org repos")),
     }
 
-    match client.list_repos(org, OwnerType::User).await {
-        Ok(repos) => {
-            let mut names: Vec<String> = repos
-                .into_iter()
-                .filter(|r| !r.archived)
-                .map(|r| r.name)
-                .collect();
-            names.sort();
-            Ok(names)
+    fn usage(&self) -> ApiUsage {
+        let remaining = self.remaining.load(Ordering::Relaxed);
+        ApiUsage {
+            requests: self.requests.load(Ordering::Relaxed),
+            remaining: (remaining != u64::MAX).then_some(remaining),
         }
-        Err(e) => Err(anyhow::anyhow!("{e}").context("fetching user repos")),
     }
 }
 
+static API_COUNTER: ApiCounter = ApiCounter::new();
+
+/// GitHub API requests this process has made so far.
+pub fn api_usage() -> ApiUsage {
+    API_COUNTER.usage()
+}
+
+/// Non-archived repos owned by `owner` from a search response's `items`,
+/// plus the raw number of items (decides whether another page follows).
+fn parse_search_page(body: &serde_json::Value, owner: &str) -> (Vec<DiscoveredRepo>, usize) {
+    let items = body
+        .get("items")
+        .and_then(|i| i.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let found = items
+        .iter()
+        .filter(|r| !r.get("archived").and_then(|a| a.as_bool()).unwrap_or(false))
+        .filter(|r| {
+            r.pointer("/owner/login")
+                .and_then(|l| l.as_str())
+                .is_some_and(|l| l.eq_ignore_ascii_case(owner))
+        })
+        .filter_map(|r| parse_discovered(r, "pushed_at"))
+        .collect();
+    (found, items.len())
+}
+
+/// Repo listing endpoint for an org or user: `{api_base}/{kind}/{owner}/repos`.
+/// Enterprise bases already carry their `/api/v3` prefix.
+fn repos_url(api_base: &str, kind: &str, owner: &str) -> String {
+    format!("{}/{kind}/{owner}/repos", api_base.trim_end_matches('/'))
+}
+
+/// Repos per page of a GitHub listing (the API maximum).
+const LISTING_PER_PAGE: usize = 100;
+
+/// Page through a repo listing `url`, skipping archived repos.
+/// Returns `None` when the owner doesn't exist there (404).
+///
+/// Page 1 is fetched alone, since most owners fit on it. When its `Link`
+/// header names a last page, four workers fetch the rest, each request still
+/// waiting for an `--api-jobs` permit. Without one (a `304` may omit it), full
+/// pages are followed one at a time until a short one. On a TTY a spinner
+/// counts the repos found so far.
+///
+/// A page in `cached` is requested with `If-None-Match`, and a `304` reuses
+/// its repos. Pages served with an `ETag` are returned for the next revalidation.
+async fn list_github_repos(
+    client: &reqwest::Client,
+    token: Option<&str>,
+    url: &str,
+    cached: &[ListingPage],
+    limiter: &Semaphore,
+) -> Result<Option<(Vec<DiscoveredRepo>, Vec<ListingPage>)>> {
+    let fetch = |page| list_github_page(client, token, url, page, cached, limiter);
+    let Some((first, last)) = fetch(1).await? else {
+        return Ok(None);
+    };
+    let progress = display::RepoProgress::new(display::verbosity() < display::Verbosity::Normal);
+    let spinner = progress.spinner(format!("listing {url}"));
+    let found = AtomicUsize::new(first.repos.len());
+    let mut listings = vec![first];
+
+    if let Some(last) = last.filter(|&last| last > 1) {
+        let next = AtomicUsize::new(2);
+        let worker = || async {
+            let mut done = Vec::new();
+            loop {
+                let page = next.fetch_add(1, Ordering::Relaxed);
+                if page > last {
+                    return Ok::<_, anyhow::Error>(done);
+                }
+                // A 404 past page 1 means the listing shrank meanwhile
+                let Some((listing, _)) = fetch(page).await? else {
+                    return Ok(done);
+                };
+                let so_far = found.fetch_add(listing.repos.len(), Ordering::Relaxed) + listing.repos.len();
+                spinner.set_message(format!("listing {url}: {so_far} repos so far"));
+                done.push(listing);
+            }
+        };
+        let (a, b, c, d) = tokio::join!(worker(), worker(), worker(), worker());
+        for done in [a?, b?, c?, d?] {
+            listings.extend(done);
+        }
+        listings.sort_by_key(|listing| listing.page);
+    } else {
+        while let Some(previous) = listings.last().filter(|l| l.count >= LISTING_PER_PAGE) {
+            let Some((listing, _)) = fetch(previous.page + 1).await? else {
+                break;
+            };
+            let so_far = found.fetch_add(listing.repos.len(), Ordering::Relaxed) + listing.repos.len();
+            spinner.set_message(format!("listing {url}: {so_far} repos so far"));
+            listings.push(listing);
+        }
+    }
+
+    let repos = listings.iter().flat_map(|listing| listing.repos.iter().cloned()).collect();
+    let pages = listings.into_iter().filter(|listing| !listing.etag.is_empty()).collect();
+    Ok(Some((repos, pages)))
+}
+
+/// Fetch one page of a GitHub repo listing, with the last page number its
+/// `Link` header names; `None` on 404.
+async fn list_github_page(
+    client: &reqwest::Client,
+    token: Option<&str>,
+    url: &str,
+    page: usize,
+    cached: &[ListingPage],
+    limiter: &Semaphore,
+) -> Result<Option<(ListingPage, Option<usize>)>> {
+    let mut req = client
+        .get(url)
+        .query(&[("per_page", LISTING_PER_PAGE.to_string()), ("page", page.to_string())])
+        .header("Accept", "application/vnd.github+json");
+    if let Some(token) = token {
+        req = req.bearer_auth(token.trim());
+    }
+    let cached_page = cached.iter().find(|p| p.url == url && p.page == page);
+    if let Some(cached_page) = cached_page {
+        req = req.header("If-None-Match", &cached_page.etag);
+    }
+
+    let resp = send_github(req, url, limiter).await?;
+    let last = last_page(resp.headers());
+    let status = resp.status();
+    if let Some(cached_page) = cached_page.filter(|_| status == reqwest::StatusCode::NOT_MODIFIED) {
+        return Ok(Some((cached_page.clone(), last)));
+    }
+    if status == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !status.is_success() {
+        return Err(Failure::Network.tag(anyhow::anyhow!("GET {url} returned {status}")));
+    }
+    let etag = resp
+        .headers()
+        .get(reqwest::header::ETAG)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let body: serde_json::Value = resp.json().await.context("parsing repo list")?;
+    let (page_repos, count) = parse_github_repos_page(&body);
+    let listing = ListingPage {
+        url: url.to_string(),
+        page,
+        etag: etag.unwrap_or_default(),
+        repos: page_repos,
+        count,
+    };
+    Ok(Some((listing, last)))
+}
+
+/// The `page` query parameter of the `rel="last"` URL in a `Link` header.
+fn last_page(headers: &reqwest::header::HeaderMap) -> Option<usize> {
+    let link = headers.get(reqwest::header::LINK)?.to_str().ok()?;
+    let target = link.split(',').find(|part| part.contains(r#"rel="last""#))?;
+    let url = target.split_once('<')?.1.split_once('>')?.0;
+    url.split_once('?')?
+        .1
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("page="))?
+        .parse()
+        .ok()
+}
+
+/// Non-archived repos from a GitHub repo list page, plus the page's raw length.
+fn parse_github_repos_page(body: &serde_json::Value) -> (Vec<DiscoveredRepo>, usize) {
+    let repos = body.as_array().map(Vec::as_slice).unwrap_or_default();
+    let found = repos
+        .iter()
+        .filter(|r| !r.get("archived").and_then(|a| a.as_bool()).unwrap_or(false))
+        .filter_map(|r| parse_discovered(r, "pushed_at"))
+        .collect();
+    (found, repos.len())
+}
+
+/// Read `name`, the timestamp under `pushed_key`, and the visibility from a
+/// repo JSON object. Sourcehut's `unlisted` repos count as public.
+fn parse_discovered(repo: &serde_json::Value, pushed_key: &str) -> Option<DiscoveredRepo> {
+    Some(DiscoveredRepo {
+        name: repo.get("name")?.as_str()?.to_string(),
+        pushed_at: repo
+            .get(pushed_key)
+            .and_then(|p| p.as_str())
+            .map(str::to_string),
+        private: repo.get("private").and_then(|p| p.as_bool()).or_else(|| {
+            repo.get("visibility")
+                .and_then(|v| v.as_str())
+                .map(|v| v.eq_ignore_ascii_case("private"))
+        }),
+    })
+}
+
+/// Discover repos by running a workspace's `provider_command` (`provider: command`).
+///
+/// The contract: the executable gets `owner` as its only argument and on stdin,
+/// exits 0, and prints one repo name per line on stdout (blank lines are
+/// ignored). Whatever it writes to stderr is passed through as diagnostics;
+/// a nonzero exit fails discovery.
+pub fn discover_command_repos(
+    command: &str,
+    owner: &str,
+    env: &HashMap<String, String>,
+) -> Result<Vec<DiscoveredRepo>> {
+    let program = shellexpand::tilde(command);
+    let mut child = Command::new(program.as_ref())
+        .arg(owner)
+        .envs(env)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .with_context(|| format!("running provider_command {command}"))
+        .map_err(|e| Failure::Config.tag(e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A command that never reads stdin may exit first; that's not an error
+        let _ = writeln!(stdin, "{owner}");
+    }
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("waiting for provider_command {command}"))?;
+    if !output.status.success() {
+        return Err(Failure::Network.tag(anyhow::anyhow!(
+            "provider_command {command} failed for {owner} ({})",
+            output.status
+        )));
+    }
+
+    let mut repos: Vec<DiscoveredRepo> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|name| DiscoveredRepo {
+            name: name.to_string(),
+            pushed_at: None,
+            private: None,
+        })
+        .collect();
+    repos.sort_by(|a, b| a.name.cmp(&b.name));
+    repos.dedup();
+    Ok(repos)
+}
+
+/// Base URL of the git.sr.ht legacy REST API.
+const SRHT_API: &str = "https://git.sr.ht/api";
+
+/// Discover all repos owned by a Sourcehut user via `GET /api/~user/repos`.
+/// Follows the cursor in each page's `next` field until it is null.
+/// Authenticates with `token` as a Bearer token when given (needed for private repos).
+pub async fn discover_sourcehut_repos(
+    user: &str,
+    token: Option<&str>,
+    limiter: &Semaphore,
+) -> Result<Vec<DiscoveredRepo>> {
+    let user = user.trim_start_matches('~');
+    let client = api_client()?;
+    let mut repos = Vec::new();
+    let mut cursor: Option<String> = None;
+
+    loop {
+        let mut req = client.get(format!("{SRHT_API}/~{user}/repos"));
+        if let Some(ref start) = cursor {
+            req = req.query(&[("start", start)]);
+        }
+        if let Some(token) = token {
+            req = req.bearer_auth(token.trim());
+        }
+
+        let permit = limiter.acquire().await.context("API limiter closed")?;
+        let resp = req
+            .send()
+            .await
+            .with_context(|| format!("requesting repos for ~{user}"))?;
+        drop(permit);
+
+        let status = resp.status();
+        if !status.is_success() {
+            return Err(Failure::Network.tag(anyhow::anyhow!(
+                "git.sr.ht returned {status} listing repos for ~{user}"
+            )));
+        }
+        let body: serde_json::Value = resp.json().await.context("parsing git.sr.ht response")?;
+        let (page, next) = parse_srht_page(&body);
+        repos.extend(page);
+
+        match next {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+
+    repos.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(repos)
+}
+
+/// Split a git.sr.ht page into repos and the `next` cursor (absent on the last page).
+fn parse_srht_page(body: &serde_json::Value) -> (Vec<DiscoveredRepo>, Option<String>) {
+    let repos = body
+        .get("results")
+        .and_then(|r| r.as_array())
+        .map(|results| {
+            results
+                .iter()
+                .filter_map(|r| parse_discovered(r, "updated"))
+                .collect()
+        })
+        .unwrap_or_default();
+    let next = match body.get("next") {
+        Some(serde_json::Value::String(s)) if !s.is_empty() => Some(s.clone()),
+        Some(serde_json::Value::Number(n)) => Some(n.to_string()),
+        _ => None,
+    };
+    (repos, next)
+}
+
+/// Get the Sourcehut token from environment (TEND_SRHT_TOKEN).
+pub fn srht_token() -> Option<String> {
+    std::env::var("TEND_SRHT_TOKEN").ok()
+}
+
+/// Base URL of the public GitHub REST API.
+const GITHUB_API: &str = "https://api.github.com";
+
+/// Scopes a classic token needs to discover and clone private repos.
+const REQUIRED_SCOPES: &[&str] = &["repo"];
+
+/// Identity behind the configured GitHub token.
+#[derive(Debug)]
+pub enum TokenInfo {
+    /// No TEND_GITHUB_TOKEN/GITHUB_TOKEN in the environment
+    Unauthenticated,
+    /// Token accepted by the API
+    Authenticated {
+        login: String,
+        /// Granted OAuth scopes; `None` for fine-grained tokens, which don't report scopes
+        scopes: Option<Vec<String>>,
+    },
+}
+
+impl TokenInfo {
+    /// Required scopes the token lacks (empty when unknown or unauthenticated).
+    pub fn missing_scopes(&self) -> Vec<&'static str> {
+        match self {
+            TokenInfo::Authenticated { scopes: Some(scopes), .. } => REQUIRED_SCOPES
+                .iter()
+                .filter(|req| !scopes.iter().any(|s| s == *req))
+                .copied()
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Env var naming a PEM file of extra root certificates (e.g. a corporate CA).
+const CA_BUNDLE_ENV: &str = "TEND_CA_BUNDLE";
+
+/// Env var overriding the HTTP timeout, in whole seconds.
+const HTTP_TIMEOUT_ENV: &str = "TEND_HTTP_TIMEOUT";
+
+/// Connect and per-request timeout when `TEND_HTTP_TIMEOUT` is unset.
+const DEFAULT_HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Parse a `TEND_HTTP_TIMEOUT` value; unset or empty means the default.
+fn parse_http_timeout(value: Option<&str>) -> Result<Duration> {
+    match value.map(str::trim).filter(|v| !v.is_empty()) {
+        None => Ok(DEFAULT_HTTP_TIMEOUT),
+        Some(v) => match v.parse::<u64>() {
+            Ok(secs) if secs > 0 => Ok(Duration::from_secs(secs)),
+            _ => anyhow::bail!("{HTTP_TIMEOUT_ENV} must be a positive number of seconds, got '{v}'"),
+        },
+    }
+}
+
+/// Build the HTTP client for provider APIs.
+///
+/// Connecting and each whole request time out after 30s (`TEND_HTTP_TIMEOUT`
+/// seconds), so a stalled API call fails instead of hanging a daemon cycle.
+/// Proxies come from reqwest's own `HTTP(S)_PROXY`/`ALL_PROXY`/`NO_PROXY`
+/// handling. Trusts the certificates in `TEND_CA_BUNDLE` in addition to the
+/// system roots.
+pub(crate) fn api_client() -> Result<reqwest::Client> {
+    let timeout = parse_http_timeout(std::env::var(HTTP_TIMEOUT_ENV).ok().as_deref())?;
+    let mut builder = reqwest::Client::builder()
+        .user_agent(concat!("tend/", env!("CARGO_PKG_VERSION")))
+        .connect_timeout(timeout)
+        .timeout(timeout);
+
+    if let Some(path) = std::env::var_os(CA_BUNDLE_ENV).filter(|p| !p.is_empty()) {
+        let path = std::path::PathBuf::from(path);
+        let pem = std::fs::read(&path)
+            .with_context(|| format!("reading {CA_BUNDLE_ENV} {}", path.display()))?;
+        let certs = reqwest::Certificate::from_pem_bundle(&pem)
+            .with_context(|| format!("parsing certificates in {}", path.display()))?;
+        for cert in certs {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+
+    builder.build().context("building HTTP client")
+}
+
+/// Report the user and OAuth scopes for the configured token via `GET /user`.
+pub async fn token_info() -> Result<TokenInfo> {
+    let token = match github_token() {
+        Some(t) if !t.trim().is_empty() => t,
+        _ => return Ok(TokenInfo::Unauthenticated),
+    };
+
+    let req = api_client()?
+        .get(format!("{GITHUB_API}/user"))
+        .bearer_auth(token.trim())
+        .header("Accept", "application/vnd.github+json");
+    let resp = send_counted(req, "GET /user").await?;
+
+    let status = resp.status();
+    if status == reqwest::StatusCode::UNAUTHORIZED {
+        return Err(Failure::Network.tag(anyhow::anyhow!(
+            "GitHub rejected the token (401): it is invalid or expired"
+        )));
+    }
+    if !status.is_success() {
+        return Err(Failure::Network.tag(anyhow::anyhow!("GET /user returned {status}")));
+    }
+
+    let scopes = resp
+        .headers()
+        .get("x-oauth-scopes")
+        .and_then(|v| v.to_str().ok())
+        .map(parse_scopes);
+    let body: serde_json::Value = resp.json().await.context("parsing GET /user response")?;
+    let login = body
+        .get("login")
+        .and_then(|v| v.as_str())
+        .unwrap_or("?")
+        .to_string();
+
+    Ok(TokenInfo::Authenticated { login, scopes })
+}
+
+/// One category of the GitHub API rate limit.
+#[derive(Debug, PartialEq)]
+pub struct RateLimitBucket {
+    pub limit: u64,
+    pub remaining: u64,
+    /// Unix time the window resets
+    pub reset: i64,
+}
+
+/// Current GitHub API budget from `GET /rate_limit`.
+#[derive(Debug)]
+pub struct RateLimits {
+    /// Whether a token was sent (5000/hr core) or not (60/hr core)
+    pub authenticated: bool,
+    pub core: RateLimitBucket,
+    pub search: RateLimitBucket,
+}
+
+/// Fetch the current rate limits, authenticated with the configured token when
+/// there is one. Checking the rate limit doesn't count against it.
+pub async fn rate_limit() -> Result<RateLimits> {
+    let token = github_token().filter(|t| !t.trim().is_empty());
+
+    let mut req = api_client()?
+        .get(format!("{GITHUB_API}/rate_limit"))
+        .header("Accept", "application/vnd.github+json");
+    if let Some(ref token) = token {
+        req = req.bearer_auth(token.trim());
+    }
+    let resp = send_counted(req, "GET /rate_limit").await?;
+
+    let status = resp.status();
+    if status == reqwest::StatusCode::UNAUTHORIZED {
+        return Err(Failure::Network.tag(anyhow::anyhow!(
+            "GitHub rejected the token (401): it is invalid or expired"
+        )));
+    }
+    if !status.is_success() {
+        return Err(Failure::Network.tag(anyhow::anyhow!("GET /rate_limit returned {status}")));
+    }
+
+    let body: serde_json::Value = resp.json().await.context("parsing GET /rate_limit response")?;
+    Ok(RateLimits {
+        authenticated: token.is_some(),
+        core: parse_rate_limit_bucket(&body, "core")?,
+        search: parse_rate_limit_bucket(&body, "search")?,
+    })
+}
+
+/// Read `resources.<category>` from a `GET /rate_limit` response.
+fn parse_rate_limit_bucket(body: &serde_json::Value, category: &str) -> Result<RateLimitBucket> {
+    let bucket = &body["resources"][category];
+    let field = |name: &str| {
+        bucket[name]
+            .as_i64()
+            .with_context(|| format!("rate_limit response has no resources.{category}.{name}"))
+    };
+    Ok(RateLimitBucket {
+        limit: field("limit")?.max(0) as u64,
+        remaining: field("remaining")?.max(0) as u64,
+        reset: field("reset")?,
+    })
+}
+
+/// Parse a comma-separated `X-OAuth-Scopes` header value.
+fn parse_scopes(header: &str) -> Vec<String> {
+    header
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
 /// Get the auth token from environment (TEND_GITHUB_TOKEN or GITHUB_TOKEN).
 pub fn github_token() -> Option<String> {
     std::env::var("TEND_GITHUB_TOKEN")
@@ -89,6 +891,149 @@ pub(crate) fn normalize_language(lang: &str) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_repos_url_public_and_enterprise() {
+        assert_eq!(
+            repos_url(GITHUB_API, "orgs", "pleme-io"),
+            "https://api.github.com/orgs/pleme-io/repos"
+        );
+        assert_eq!(
+            repos_url("https://github.example.com/api/v3/", "orgs", "acme"),
+            "https://github.example.com/api/v3/orgs/acme/repos"
+        );
+        assert_eq!(
+            repos_url("https://github.example.com/api/v3", "users", "alice"),
+            "https://github.example.com/api/v3/users/alice/repos"
+        );
+    }
+
+    fn repo(name: &str, pushed_at: Option<&str>) -> DiscoveredRepo {
+        DiscoveredRepo {
+            name: name.to_string(),
+            pushed_at: pushed_at.map(str::to_string),
+            private: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_github_repos_page_skips_archived() {
+        let body = serde_json::json!([
+            {"name": "live", "archived": false, "pushed_at": "2024-05-01T12:00:00Z"},
+            {"name": "old", "archived": true},
+            {"name": "no-flag"},
+        ]);
+        assert_eq!(
+            parse_github_repos_page(&body),
+            (vec![repo("live", Some("2024-05-01T12:00:00Z")), repo("no-flag", None)], 3)
+        );
+    }
+
+    #[test]
+    fn test_parse_search_page_keeps_owned_live_repos() {
+        let body = serde_json::json!({
+            "total_count": 4,
+            "incomplete_results": false,
+            "items": [
+                {"name": "api", "owner": {"login": "Acme"}, "pushed_at": "2024-05-01T12:00:00Z"},
+                {"name": "old", "owner": {"login": "acme"}, "archived": true},
+                {"name": "fork", "owner": {"login": "someone"}},
+                {"name": "web", "owner": {"login": "acme"}},
+            ]
+        });
+        assert_eq!(
+            parse_search_page(&body, "acme"),
+            (vec![repo("api", Some("2024-05-01T12:00:00Z")), repo("web", None)], 4)
+        );
+    }
+
+    #[test]
+    fn test_rate_limit_wait() {
+        use reqwest::header::{HeaderMap, HeaderValue};
+        use reqwest::StatusCode;
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("0"));
+        headers.insert("x-ratelimit-reset", HeaderValue::from_static("1000"));
+        assert_eq!(rate_limit_wait(StatusCode::FORBIDDEN, &headers, 990), Some(Duration::from_secs(11)));
+        assert_eq!(rate_limit_wait(StatusCode::OK, &headers, 990), None);
+
+        headers.insert("retry-after", HeaderValue::from_static("5"));
+        assert_eq!(rate_limit_wait(StatusCode::TOO_MANY_REQUESTS, &headers, 990), Some(Duration::from_secs(5)));
+
+        let mut forbidden = HeaderMap::new();
+        forbidden.insert("x-ratelimit-remaining", HeaderValue::from_static("12"));
+        assert_eq!(rate_limit_wait(StatusCode::FORBIDDEN, &forbidden, 990), None);
+    }
+
+    #[test]
+    fn test_search_cache_key_keeps_operators_distinct() {
+        assert_eq!(search_cache_key("acme", "topic:x"), "acme_topic_3ax");
+        assert_ne!(search_cache_key("acme", "stars:>10"), search_cache_key("acme", "stars:<10"));
+    }
+
+    #[test]
+    fn test_parse_srht_page() {
+        let body = serde_json::json!({
+            "next": "42",
+            "results": [{"name": "alpha", "updated": "2023-01-02T03:04:05+00:00"}, {"name": "beta"}],
+            "total": 3,
+        });
+        assert_eq!(
+            parse_srht_page(&body),
+            (
+                vec![repo("alpha", Some("2023-01-02T03:04:05+00:00")), repo("beta", None)],
+                Some("42".to_string())
+            )
+        );
+
+        let last = serde_json::json!({"next": null, "results": [{"name": "gamma"}]});
+        assert_eq!(parse_srht_page(&last), (vec![repo("gamma", None)], None));
+
+        let numeric = serde_json::json!({"next": 7, "results": []});
+        assert_eq!(parse_srht_page(&numeric).1, Some("7".to_string()));
+    }
+
+    #[test]
+    fn test_discover_command_repos_follows_contract() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = std::env::temp_dir().join(format!("tend-test-provider-cmd-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("forge");
+        // Owner arrives as $1 and on stdin; env comes from the workspace
+        std::fs::write(
+            &script,
+            "#!/bin/sh\nread from_stdin\necho \"$1-api\"\necho\necho \"$from_stdin-$FORGE_SUFFIX\"\necho note >&2\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let failing = dir.join("down");
+        std::fs::write(&failing, "#!/bin/sh\nexit 3\n").unwrap();
+        std::fs::set_permissions(&failing, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let env = HashMap::from([("FORGE_SUFFIX".to_string(), "web".to_string())]);
+        let names: Vec<String> = discover_command_repos(script.to_str().unwrap(), "acme", &env)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.name)
+            .collect();
+        assert_eq!(names, ["acme-api", "acme-web"]);
+
+        let err = discover_command_repos(failing.to_str().unwrap(), "acme", &env).unwrap_err();
+        assert_eq!(crate::exit::exit_code(&err), 3);
+        assert!(discover_command_repos("/nonexistent/forge", "acme", &env).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_parse_discovered_reads_visibility() {
+        let private = |repo: serde_json::Value| parse_discovered(&repo, "pushed_at").unwrap().private;
+        assert_eq!(private(serde_json::json!({"name": "a", "private": true})), Some(true));
+        assert_eq!(private(serde_json::json!({"name": "a", "private": false})), Some(false));
+        assert_eq!(private(serde_json::json!({"name": "a", "visibility": "PRIVATE"})), Some(true));
+        assert_eq!(private(serde_json::json!({"name": "a", "visibility": "unlisted"})), Some(false));
+        assert_eq!(private(serde_json::json!({"name": "a"})), None);
+    }
+
     #[test]
     fn test_normalize_language() {
         assert_eq!(normalize_language("Go"), "go");
@@ -100,4 +1045,259 @@ mod tests {
         assert_eq!(normalize_language("C#"), "csharp");
         assert_eq!(normalize_language("Fortran"), "fortran");
     }
+
+    #[test]
+    fn test_parse_scopes() {
+        assert_eq!(parse_scopes("repo, read:org,gist"), vec!["repo", "read:org", "gist"]);
+        assert!(parse_scopes("").is_empty());
+    }
+
+    #[test]
+    fn test_missing_scopes() {
+        let info = TokenInfo::Authenticated {
+            login: "me".to_string(),
+            scopes: Some(vec!["read:org".to_string()]),
+        };
+        assert_eq!(info.missing_scopes(), vec!["repo"]);
+
+        let info = TokenInfo::Authenticated {
+            login: "me".to_string(),
+            scopes: Some(vec!["repo".to_string(), "read:org".to_string()]),
+        };
+        assert!(info.missing_scopes().is_empty());
+
+        let info = TokenInfo::Authenticated { login: "me".to_string(), scopes: None };
+        assert!(info.missing_scopes().is_empty());
+        assert!(TokenInfo::Unauthenticated.missing_scopes().is_empty());
+    }
+
+    #[test]
+    fn test_parse_http_timeout() {
+        assert_eq!(parse_http_timeout(None).unwrap(), DEFAULT_HTTP_TIMEOUT);
+        assert_eq!(parse_http_timeout(Some("")).unwrap(), DEFAULT_HTTP_TIMEOUT);
+        assert_eq!(parse_http_timeout(Some(" 5 ")).unwrap(), Duration::from_secs(5));
+        assert!(parse_http_timeout(Some("0")).is_err());
+        assert!(parse_http_timeout(Some("30s")).is_err());
+    }
+
+    #[test]
+    fn test_api_counter_keeps_lowest_core_remaining() {
+        use reqwest::header::{HeaderMap, HeaderValue};
+
+        let counter = ApiCounter::new();
+        assert_eq!(counter.usage(), ApiUsage { requests: 0, remaining: None });
+
+        let response = |resource: &'static str, remaining: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert("x-ratelimit-resource", HeaderValue::from_static(resource));
+            headers.insert("x-ratelimit-remaining", HeaderValue::from_static(remaining));
+            headers
+        };
+        counter.record(&response("core", "4990"));
+        counter.record(&response("core", "4995"));
+        counter.record(&response("search", "12"));
+        counter.record(&HeaderMap::new());
+        assert_eq!(counter.usage(), ApiUsage { requests: 4, remaining: Some(4990) });
+    }
+
+    #[test]
+    fn test_parse_rate_limit_bucket() {
+        let body = serde_json::json!({
+            "resources": {
+                "core": {"limit": 5000, "remaining": 4321, "reset": 1700000000, "used": 679},
+                "search": {"limit": 30, "remaining": 30, "reset": 1700000060}
+            }
+        });
+        assert_eq!(
+            parse_rate_limit_bucket(&body, "core").unwrap(),
+            RateLimitBucket { limit: 5000, remaining: 4321, reset: 1700000000 }
+        );
+        assert_eq!(parse_rate_limit_bucket(&body, "search").unwrap().limit, 30);
+        assert!(parse_rate_limit_bucket(&body, "graphql").is_err());
+    }
+
+    /// Answer every request with `respond(request)` and collect the raw requests.
+    async fn mock_server(respond: fn(&str) -> String) -> (String, std::sync::Arc<std::sync::Mutex<Vec<String>>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let requests = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = std::sync::Arc::clone(&requests);
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 4096];
+                let n = stream.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]).to_lowercase();
+                let response = respond(&request);
+                seen.lock().unwrap().push(request);
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        (base, requests)
+    }
+
+    /// Request paths without their query strings.
+    fn paths(requests: &std::sync::Mutex<Vec<String>>) -> Vec<String> {
+        let mut requests = requests.lock().unwrap();
+        let paths = requests
+            .iter()
+            .map(|r| r.split_whitespace().nth(1).unwrap_or("").split('?').next().unwrap().to_string())
+            .collect();
+        requests.clear();
+        paths
+    }
+
+    #[tokio::test]
+    async fn test_owner_kind_picks_endpoints() {
+        let limiter = Semaphore::new(1);
+        let (base, requests) =
+            mock_server(|_| "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()).await;
+
+        let err = discover_github_listing(&base, "ghost", OwnerKind::Org, &[], &limiter).await.unwrap_err();
+        assert_eq!(err.to_string(), "no GitHub org named ghost");
+        assert_eq!(paths(&requests), ["/orgs/ghost/repos"]);
+
+        let err = discover_github_listing(&base, "ghost", OwnerKind::User, &[], &limiter).await.unwrap_err();
+        assert_eq!(err.to_string(), "no GitHub user named ghost");
+        assert_eq!(paths(&requests), ["/users/ghost/repos"]);
+
+        let err = discover_github_listing(&base, "ghost", OwnerKind::Auto, &[], &limiter).await.unwrap_err();
+        assert_eq!(err.to_string(), "no GitHub org or user named ghost");
+        assert_eq!(paths(&requests), ["/orgs/ghost/repos", "/users/ghost/repos"]);
+    }
+
+    #[tokio::test]
+    async fn test_search_retries_after_rate_limit() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        let limiter = Semaphore::new(1);
+        let (base, requests) = mock_server(|_| {
+            if CALLS.fetch_add(1, Ordering::SeqCst) == 0 {
+                return "HTTP/1.1 403 Forbidden\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string();
+            }
+            let body = r#"{"total_count":1,"items":[{"name":"api","owner":{"login":"acme"}}]}"#;
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+        })
+        .await;
+
+        let before = api_usage().requests;
+        let repos = search_repos(&base, "acme", "org:acme topic:platform", &limiter).await.unwrap();
+        assert_eq!(repos, vec![repo("api", None)]);
+        assert_eq!(paths(&requests), ["/search/repositories", "/search/repositories"]);
+        // The rate-limited attempt counts too (other tests may add to the global count)
+        assert!(api_usage().requests >= before + 2);
+    }
+
+    /// Mock listing response for `request`: `count(page)` repos named
+    /// `r{page}-{i}`, with a `Link` header naming page `last` when given.
+    fn listing_response(request: &str, last: Option<usize>, count: impl Fn(usize) -> usize) -> String {
+        let page: usize = request
+            .split("&page=")
+            .nth(1)
+            .and_then(|rest| rest.split(|c: char| !c.is_ascii_digit()).next())
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(1);
+        let names: Vec<String> = (0..count(page)).map(|i| format!(r#"{{"name":"r{page}-{i}"}}"#)).collect();
+        let body = format!("[{}]", names.join(","));
+        let link = last
+            .map(|last| format!("Link: <https://api.github.com/orgs/acme/repos?per_page=100&page={last}>; rel=\"last\"\r\n"))
+            .unwrap_or_default();
+        format!(
+            "HTTP/1.1 200 OK\r\n{link}Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )
+    }
+
+    #[tokio::test]
+    async fn test_listing_fetches_every_page_the_link_header_names() {
+        let limiter = Semaphore::new(2);
+        // Nine full pages and a short tenth: more than the four pages fetched at once
+        let (base, requests) = mock_server(|request| {
+            listing_response(request, Some(10), |page| if page < 10 { LISTING_PER_PAGE } else { 5 })
+        })
+        .await;
+
+        let (repos, _) = discover_github_listing(&base, "acme", OwnerKind::Org, &[], &limiter).await.unwrap();
+        assert_eq!(repos.len(), 9 * LISTING_PER_PAGE + 5);
+        assert!(repos.iter().any(|r| r.name == "r10-4"));
+        assert_eq!(paths(&requests).len(), 10);
+    }
+
+    #[tokio::test]
+    async fn test_listing_without_link_header_follows_full_pages() {
+        let limiter = Semaphore::new(1);
+        let (base, requests) = mock_server(|request| {
+            listing_response(request, None, |page| if page < 3 { LISTING_PER_PAGE } else { 5 })
+        })
+        .await;
+
+        let (repos, _) = discover_github_listing(&base, "acme", OwnerKind::Org, &[], &limiter).await.unwrap();
+        assert_eq!(repos.len(), 2 * LISTING_PER_PAGE + 5);
+        assert_eq!(paths(&requests).len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_listing_retries_rate_limited_pages() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        let limiter = Semaphore::new(1);
+        let (base, requests) = mock_server(|request| {
+            if CALLS.fetch_add(1, Ordering::SeqCst) == 1 {
+                return "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    .to_string();
+            }
+            listing_response(request, Some(2), |page| if page == 1 { LISTING_PER_PAGE } else { 1 })
+        })
+        .await;
+
+        let (repos, _) = discover_github_listing(&base, "acme", OwnerKind::Org, &[], &limiter).await.unwrap();
+        assert_eq!(repos.len(), LISTING_PER_PAGE + 1);
+        assert_eq!(paths(&requests).len(), 3);
+    }
+
+    #[test]
+    fn test_last_page_reads_the_link_header() {
+        use reqwest::header::{HeaderMap, HeaderValue};
+        let mut headers = HeaderMap::new();
+        assert_eq!(last_page(&headers), None);
+        headers.insert(
+            "link",
+            HeaderValue::from_static(
+                r#"<https://api.github.com/orgs/acme/repos?per_page=100&page=2>; rel="next", <https://api.github.com/orgs/acme/repos?per_page=100&page=7>; rel="last""#,
+            ),
+        );
+        assert_eq!(last_page(&headers), Some(7));
+    }
+
+    #[tokio::test]
+    async fn test_listing_revalidates_with_etag() {
+        let limiter = Semaphore::new(1);
+        let (base, requests) = mock_server(|request| {
+            if request.contains("if-none-match: \"v1\"") {
+                return "HTTP/1.1 304 Not Modified\r\nETag: \"v1\"\r\nConnection: close\r\n\r\n".to_string();
+            }
+            let body = r#"[{"name":"beta","pushed_at":null},{"name":"alpha","pushed_at":null}]"#;
+            format!(
+                "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+        })
+        .await;
+
+        let (repos, pages) = discover_github_listing(&base, "acme", OwnerKind::Org, &[], &limiter).await.unwrap();
+        let names: Vec<&str> = repos.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["alpha", "beta"]);
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].etag, "\"v1\"");
+
+        let (again, pages_again) = discover_github_listing(&base, "acme", OwnerKind::Org, &pages, &limiter).await.unwrap();
+        assert_eq!(again, repos);
+        assert_eq!(pages_again, pages);
+        let requests = requests.lock().unwrap();
+        assert!(!requests[0].contains("if-none-match"));
+        assert!(requests[1].contains("if-none-match: \"v1\""));
+    }
 }
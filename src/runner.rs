@@ -0,0 +1,85 @@
+use std::io;
+use std::path::Path;
+use std::process::{Command, ExitStatus, Output};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set by the global `--dry-run`: commands that change a repo are printed instead of run.
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+pub fn set_dry_run(on: bool) {
+    DRY_RUN.store(on, Ordering::Relaxed);
+}
+
+pub fn is_dry_run() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
+/// Run a git/nix command that changes state and capture its output. Under
+/// `--dry-run` the command is printed and an empty, successful output returned.
+///
+/// Read-only queries (`git status`, `git rev-parse`, ...) call `Command` directly
+/// so a dry run still decides what to do from the repos' real state.
+pub fn output(cmd: &mut Command) -> io::Result<Output> {
+    output_as(cmd, is_dry_run())
+}
+
+fn output_as(cmd: &mut Command, dry_run: bool) -> io::Result<Output> {
+    if dry_run {
+        println!("{}", describe(cmd));
+        return Ok(Output {
+            status: ExitStatus::default(),
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+        });
+    }
+    cmd.output()
+}
+
+/// The command's argv, shell-quoted where needed, and the directory it runs in.
+pub fn describe(cmd: &Command) -> String {
+    let argv: Vec<String> = std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(|arg| quote(&arg.to_string_lossy()))
+        .collect();
+    let cwd = match cmd.get_current_dir() {
+        Some(dir) => dir.to_path_buf(),
+        None => std::env::current_dir().unwrap_or_else(|_| Path::new(".").to_path_buf()),
+    };
+    format!("{}  (in {})", argv.join(" "), cwd.display())
+}
+
+/// Single-quote an argument that the shell would otherwise split or expand.
+fn quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@+,%".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_quotes_args_and_names_cwd() {
+        let mut cmd = Command::new("git");
+        cmd.args(["commit", "-m", "chore: update lib"]).current_dir("/tmp/repo");
+        assert_eq!(describe(&cmd), "git commit -m 'chore: update lib'  (in /tmp/repo)");
+
+        let mut cmd = Command::new("nix");
+        cmd.args(["flake", "update", "it's"]).current_dir("/r");
+        assert_eq!(describe(&cmd), r"nix flake update 'it'\''s'  (in /r)");
+    }
+
+    #[test]
+    fn test_dry_run_output_is_synthetic_success() {
+        let output = output_as(Command::new("git").args(["definitely-not-a-git-command"]), true).unwrap();
+        assert!(output.status.success());
+        assert!(output.stdout.is_empty());
+    }
+}
@@ -0,0 +1,295 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use tokio::sync::Semaphore;
+
+use crate::config::Workspace;
+use crate::lock;
+use crate::sync::{self, RepoEntry, RepoStatus, StatusOpts};
+
+/// How often statuses are re-checked without a keypress.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A row in the browser: a workspace header or one of its repos.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Row {
+    Workspace(usize),
+    Repo(usize, usize),
+}
+
+struct WorkspaceView<'a> {
+    workspace: &'a Workspace,
+    entries: Vec<RepoEntry>,
+}
+
+struct App<'a> {
+    views: Vec<WorkspaceView<'a>>,
+    rows: Vec<Row>,
+    state: ListState,
+    message: String,
+    last_refresh: Instant,
+}
+
+impl<'a> App<'a> {
+    fn new(workspaces: &[&'a Workspace]) -> Self {
+        let views = workspaces
+            .iter()
+            .map(|ws| WorkspaceView {
+                workspace: ws,
+                entries: Vec::new(),
+            })
+            .collect();
+        let mut state = ListState::default();
+        state.select(Some(0));
+        Self {
+            views,
+            rows: Vec::new(),
+            state,
+            message: String::new(),
+            last_refresh: Instant::now(),
+        }
+    }
+
+    /// Re-check every workspace and rebuild the row list, keeping the cursor in range.
+    async fn refresh(&mut self, refresh_discovery: bool, api_limiter: &Semaphore) -> Result<()> {
+        for view in &mut self.views {
+            let repos = sync::resolve_repos(view.workspace, refresh_discovery, api_limiter).await?;
+            view.entries = sync::check_status(view.workspace, &repos, &StatusOpts::default()).await?;
+        }
+        self.rows = build_rows(&self.views);
+        let selected = self.state.selected().unwrap_or(0);
+        self.state
+            .select(Some(selected.min(self.rows.len().saturating_sub(1))));
+        self.last_refresh = Instant::now();
+        Ok(())
+    }
+
+    fn selected_row(&self) -> Option<Row> {
+        self.state.selected().and_then(|i| self.rows.get(i).copied())
+    }
+
+    fn selected_workspace(&self) -> Option<&'a Workspace> {
+        match self.selected_row()? {
+            Row::Workspace(w) | Row::Repo(w, _) => Some(self.views[w].workspace),
+        }
+    }
+
+    fn move_cursor(&mut self, delta: isize) {
+        if self.rows.is_empty() {
+            return;
+        }
+        let current = self.state.selected().unwrap_or(0) as isize;
+        let next = (current + delta).clamp(0, self.rows.len() as isize - 1);
+        self.state.select(Some(next as usize));
+    }
+
+    async fn sync_selected(&mut self, api_limiter: &Semaphore) -> Result<()> {
+        let Some(ws) = self.selected_workspace() else {
+            return Ok(());
+        };
+        let Some(_lock) = lock::WorkspaceLock::try_acquire(&ws.name)? else {
+            self.message = format!("{}: locked by another tend process", ws.name);
+            return Ok(());
+        };
+        let repos = sync::resolve_repos(ws, false, api_limiter).await?;
+        let outcome = sync::sync_repos(ws, &repos, sync::SyncOpts { quiet: true, ..Default::default() }).await?;
+        self.message = format!(
+            "{}: cloned {}, {} present, {} failed",
+            ws.name,
+            outcome.cloned,
+            outcome.present,
+            outcome.failed.len()
+        );
+        self.refresh(false, api_limiter).await
+    }
+
+    async fn fetch_selected(&mut self, api_limiter: &Semaphore) -> Result<()> {
+        let Some(ws) = self.selected_workspace() else {
+            return Ok(());
+        };
+        let Some(_lock) = lock::WorkspaceLock::try_acquire(&ws.name)? else {
+            self.message = format!("{}: locked by another tend process", ws.name);
+            return Ok(());
+        };
+        let repos = sync::resolve_repos(ws, false, api_limiter).await?;
+        let (fetched, skipped) = sync::fetch_repos(ws, &repos, true).await?;
+        self.message = format!("{}: fetched {fetched}, skipped {skipped}", ws.name);
+        self.refresh(false, api_limiter).await
+    }
+
+    fn open_selected(&mut self) -> Result<()> {
+        let Some(Row::Repo(w, r)) = self.selected_row() else {
+            self.message = "select a repo to open".to_string();
+            return Ok(());
+        };
+        let url = self.views[w].workspace.web_url(&self.views[w].entries[r].name);
+        open::that(&url).with_context(|| format!("opening {url}"))?;
+        self.message = format!("opened {url}");
+        Ok(())
+    }
+}
+
+fn build_rows(views: &[WorkspaceView<'_>]) -> Vec<Row> {
+    let mut rows = Vec::new();
+    for (w, view) in views.iter().enumerate() {
+        rows.push(Row::Workspace(w));
+        rows.extend((0..view.entries.len()).map(|r| Row::Repo(w, r)));
+    }
+    rows
+}
+
+/// Run the interactive status browser until the user quits.
+pub async fn run(workspaces: &[&Workspace], refresh: bool, api_limiter: &Semaphore) -> Result<()> {
+    let mut app = App::new(workspaces);
+    app.refresh(refresh, api_limiter).await?;
+
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, &mut app, api_limiter).await;
+    ratatui::restore();
+    result
+}
+
+async fn event_loop(
+    terminal: &mut DefaultTerminal,
+    app: &mut App<'_>,
+    api_limiter: &Semaphore,
+) -> Result<()> {
+    loop {
+        terminal.draw(|frame| draw(frame, app))?;
+
+        let timeout = REFRESH_INTERVAL.saturating_sub(app.last_refresh.elapsed());
+        if !event::poll(timeout)? {
+            app.refresh(false, api_limiter).await?;
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        let outcome = match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Down | KeyCode::Char('j') => {
+                app.move_cursor(1);
+                Ok(())
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                app.move_cursor(-1);
+                Ok(())
+            }
+            KeyCode::Char('r') => {
+                app.message = "refreshed".to_string();
+                app.refresh(false, api_limiter).await
+            }
+            KeyCode::Char('s') => app.sync_selected(api_limiter).await,
+            KeyCode::Char('f') => app.fetch_selected(api_limiter).await,
+            KeyCode::Char('o') => app.open_selected(),
+            _ => Ok(()),
+        };
+        if let Err(e) = outcome {
+            app.message = format!("error: {e:#}");
+        }
+        // git/sync warnings go straight to stderr; repaint everything
+        terminal.clear()?;
+    }
+}
+
+fn draw(frame: &mut Frame, app: &mut App<'_>) {
+    let [list_area, footer_area] =
+        Layout::vertical([Constraint::Min(1), Constraint::Length(2)]).areas(frame.area());
+
+    let items: Vec<ListItem> = app
+        .rows
+        .iter()
+        .map(|row| match *row {
+            Row::Workspace(w) => ListItem::new(Line::from(Span::styled(
+                format!("workspace: {}", app.views[w].workspace.name),
+                Style::default().add_modifier(Modifier::BOLD),
+            ))),
+            Row::Repo(w, r) => {
+                let entry = &app.views[w].entries[r];
+                let (icon, label, color) = match entry.status {
+                    RepoStatus::Clean => ("ok", "clean", Color::Green),
+                    RepoStatus::Dirty => ("!!", "dirty", Color::Yellow),
+                    RepoStatus::Missing => ("--", "missing", Color::Red),
+                    RepoStatus::Unknown => ("??", "unknown", Color::Cyan),
+                    RepoStatus::Broken => ("xx", "broken", Color::Red),
+                    RepoStatus::Detached => ("@@", "detached", Color::Magenta),
+                    RepoStatus::Mirror => ("<>", "mirror", Color::Blue),
+                    RepoStatus::InProgress(op) => ("~~", op.label(), Color::Red),
+                };
+                ListItem::new(Line::from(vec![
+                    Span::raw("  ["),
+                    Span::styled(icon, Style::default().fg(color)),
+                    Span::raw(format!("] {:<40} {label}", entry.name)),
+                ]))
+            }
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(" tend "))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    frame.render_stateful_widget(list, list_area, &mut app.state);
+
+    let footer = Paragraph::new(vec![
+        Line::from(app.message.as_str()),
+        Line::from(Span::styled(
+            "j/k move  r refresh  s sync  f fetch  o open  q quit",
+            Style::default().add_modifier(Modifier::DIM),
+        )),
+    ]);
+    frame.render_widget(footer, footer_area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_workspace(name: &str) -> Workspace {
+        Workspace {
+            name: name.to_string(),
+            base_dir: format!("/tmp/{name}"),
+            ..Default::default()
+        }
+    }
+
+    fn entry(name: &str) -> RepoEntry {
+        RepoEntry {
+            name: name.to_string(),
+            status: RepoStatus::Clean,
+            changes: Vec::new(),
+            last_commit: None,
+            stashes: 0,
+        }
+    }
+
+    #[test]
+    fn test_rows_and_cursor_navigation() {
+        let (a, b) = (make_workspace("a"), make_workspace("b"));
+        let mut app = App::new(&[&a, &b]);
+        app.views[0].entries = vec![entry("one"), entry("two")];
+        app.rows = build_rows(&app.views);
+
+        assert_eq!(
+            app.rows,
+            vec![Row::Workspace(0), Row::Repo(0, 0), Row::Repo(0, 1), Row::Workspace(1)]
+        );
+
+        app.move_cursor(-1);
+        assert_eq!(app.selected_row(), Some(Row::Workspace(0)));
+        app.move_cursor(2);
+        assert_eq!(app.selected_row(), Some(Row::Repo(0, 1)));
+        app.move_cursor(10);
+        assert_eq!(app.selected_row(), Some(Row::Workspace(1)));
+        assert_eq!(app.selected_workspace().unwrap().name, "b");
+    }
+}
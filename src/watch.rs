@@ -120,6 +120,7 @@ impl MatrixAppender for TomlMatrixAppender {
 /// and possibly languages). For workspaces with 80+ repos, this could consume
 /// 160-240 requests per cycle. With authenticated rate limits of 5000 req/hr,
 /// this supports ~20-30 cycles per hour. Adjust the daemon interval accordingly.
+#[allow(clippy::too_many_arguments)]
 pub async fn run_watch_cycle(
     ws: &Workspace,
     quiet: bool,
@@ -128,6 +129,7 @@ pub async fn run_watch_cycle(
     matrix_appender: &dyn MatrixAppender,
     git_ops: &dyn GitOps,
     audit: &AuditLog,
+    api_limiter: &tokio::sync::Semaphore,
 ) -> Result<WatchSummary> {
     let watch_cfg = ws.watch.as_ref()
         .ok_or_else(|| anyhow::anyhow!("watch not configured for workspace {}", ws.name))?;
@@ -168,7 +170,7 @@ pub async fn run_watch_cycle(
 
     // ── Repo-level watch (matrix.toml version tracking) ──
     if let Some(ref matrix_file) = matrix_file {
-        let repos = sync::resolve_repos(ws, false).await?;
+        let repos = sync::resolve_repos(ws, false, api_limiter).await?;
         let org = ws.org.as_deref().unwrap_or(&ws.name);
 
         for repo_name in &repos {
@@ -178,9 +180,7 @@ pub async fn run_watch_cycle(
             let head = match github.get_repo_head(org, repo_name).await {
                 Ok(sha) => sha,
                 Err(e) => {
-                    if !quiet {
-                        eprintln!("  warning: failed to get HEAD for {repo_name}: {e}");
-                    }
+                    display::errln!("  warning: failed to get HEAD for {repo_name}: {e}");
                     errors += 1;
                     continue;
                 }
@@ -190,9 +190,7 @@ pub async fn run_watch_cycle(
             let latest_tag = match github.get_latest_tag(org, repo_name).await {
                 Ok(tag) => tag,
                 Err(e) => {
-                    if !quiet {
-                        eprintln!("  warning: failed to get tags for {repo_name}: {e}");
-                    }
+                    display::errln!("  warning: failed to get tags for {repo_name}: {e}");
                     errors += 1;
                     continue;
                 }
@@ -227,9 +225,7 @@ pub async fn run_watch_cycle(
                     match github.detect_repo_language(org, repo_name).await {
                         Ok(lang) => lang,
                         Err(e) => {
-                            if !quiet {
-                                eprintln!("  warning: failed to detect language for {repo_name}: {e}");
-                            }
+                            display::errln!("  warning: failed to detect language for {repo_name}: {e}");
                             None
                         }
                     }
@@ -276,9 +272,7 @@ pub async fn run_watch_cycle(
                         // Repo not found in matrix or version already exists
                     }
                     Err(e) => {
-                        if !quiet {
-                            eprintln!("  warning: failed to append matrix entry for {repo_name}: {e}");
-                        }
+                        display::errln!("  warning: failed to append matrix entry for {repo_name}: {e}");
                         errors += 1;
                     }
                 }
@@ -306,7 +300,7 @@ pub async fn run_watch_cycle(
             // Step 1: Auto-certify — run `akeyless-matrix certify` to build hashes + generate Nix
             if watch_cfg.auto_certify {
                 if !quiet {
-                    eprintln!("  [>>] running akeyless-matrix certify...");
+                    display::errln!("  [>>] running akeyless-matrix certify...");
                 }
                 let certify_start = std::time::Instant::now();
                 match run_certify(matrix_file) {
@@ -317,9 +311,7 @@ pub async fn run_watch_cycle(
                     Err(e) => {
                         let duration_ms = certify_start.elapsed().as_millis() as u64;
                         audit.certify_complete(&last_repo, &last_version, "failed", duration_ms);
-                        if !quiet {
-                            eprintln!("  warning: auto-certify failed: {e}");
-                        }
+                        display::errln!("  warning: auto-certify failed: {e}");
                         errors += 1;
                     }
                 }
@@ -331,9 +323,7 @@ pub async fn run_watch_cycle(
                 &last_repo, &last_version, &last_rev, &matrix_file_str,
                 audit,
             ).await {
-                if !quiet {
-                    eprintln!("  warning: after_certify hook failed: {e}");
-                }
+                display::errln!("  warning: after_certify hook failed: {e}");
                 errors += 1;
             }
 
@@ -348,9 +338,7 @@ pub async fn run_watch_cycle(
                         audit.commit_pushed(&repo_dir, "(auto)", msg);
                     }
                     Err(e) => {
-                        if !quiet {
-                            eprintln!("  warning: auto-commit failed: {e}");
-                        }
+                        display::errln!("  warning: auto-commit failed: {e}");
                         errors += 1;
                     }
                 }
@@ -362,21 +350,17 @@ pub async fn run_watch_cycle(
                 &last_repo, &last_version, &last_rev, &matrix_file_str,
                 audit,
             ).await {
-                if !quiet {
-                    eprintln!("  warning: after_commit hook failed: {e}");
-                }
+                display::errln!("  warning: after_commit hook failed: {e}");
                 errors += 1;
             }
 
             // Step 3: Auto-propagate — run `tend flake-update --changed <repo>`
             if let Some(ref repo_name) = watch_cfg.auto_propagate {
                 if !quiet {
-                    eprintln!("  [>>] propagating flake update for {repo_name}...");
+                    display::errln!("  [>>] propagating flake update for {repo_name}...");
                 }
                 if let Err(e) = run_flake_propagate(repo_name, ws) {
-                    if !quiet {
-                        eprintln!("  warning: auto-propagate failed: {e}");
-                    }
+                    display::errln!("  warning: auto-propagate failed: {e}");
                     errors += 1;
                 }
             }
@@ -387,9 +371,7 @@ pub async fn run_watch_cycle(
                 &last_repo, &last_version, &last_rev, &matrix_file_str,
                 audit,
             ).await {
-                if !quiet {
-                    eprintln!("  warning: after_propagate hook failed: {e}");
-                }
+                display::errln!("  warning: after_propagate hook failed: {e}");
                 errors += 1;
             }
 
@@ -399,9 +381,7 @@ pub async fn run_watch_cycle(
                 &last_repo, &last_version, &last_rev, &matrix_file_str,
                 audit,
             ).await {
-                if !quiet {
-                    eprintln!("  warning: after_all hook failed: {e}");
-                }
+                display::errln!("  warning: after_all hook failed: {e}");
                 errors += 1;
             }
         }
@@ -418,12 +398,10 @@ pub async fn run_watch_cycle(
         {
             Ok(result) => result,
             Err(e) => {
-                if !quiet {
-                    eprintln!(
-                        "  warning: failed to get file SHA for {}: {e}",
-                        cache_key
-                    );
-                }
+                display::errln!(
+                    "  warning: failed to get file SHA for {}: {e}",
+                    cache_key
+                );
                 errors += 1;
                 continue;
             }
@@ -449,16 +427,16 @@ pub async fn run_watch_cycle(
         );
 
         if !quiet {
-            println!(
+            display::outln!(
                 "  {} file changed: {}",
                 "!".yellow().bold(),
                 cache_key
             );
-            println!(
+            display::outln!(
                 "    old SHA: {}",
                 cached_sha.as_deref().unwrap_or("(none)")
             );
-            println!("    new SHA: {}", new_sha);
+            display::outln!("    new SHA: {}", new_sha);
         }
 
         // Download the file if download_to is configured
@@ -506,7 +484,7 @@ pub async fn run_watch_cycle(
             );
 
             if !quiet {
-                println!("    downloaded: {}", current_file);
+                display::outln!("    downloaded: {}", current_file);
             }
 
             // Previous version (if cached)
@@ -542,7 +520,7 @@ pub async fn run_watch_cycle(
                 .map(|d| shellexpand::tilde(d).to_string());
 
             if !quiet {
-                eprintln!(
+                display::errln!(
                     "  {} running file-watch hook: {} {}",
                     "=>".blue().bold(),
                     hook.command,
@@ -563,24 +541,20 @@ pub async fn run_watch_cycle(
                     let exit_code = status.code().unwrap_or(-1);
                     audit.hook_executed("on_change", &hook.command, exit_code, duration_ms);
                     if !status.success() && !hook.continue_on_error {
-                        if !quiet {
-                            eprintln!(
-                                "  warning: file-watch hook failed: {} (exit {})",
-                                hook.command, status
-                            );
-                        }
+                        display::errln!(
+                            "  warning: file-watch hook failed: {} (exit {})",
+                            hook.command, status
+                        );
                         errors += 1;
                     }
                 }
                 Err(e) => {
                     let duration_ms = hook_start.elapsed().as_millis() as u64;
                     audit.hook_executed("on_change", &hook.command, -1, duration_ms);
-                    if !quiet {
-                        eprintln!(
-                            "  warning: file-watch hook error: {}: {e}",
-                            hook.command
-                        );
-                    }
+                    display::errln!(
+                        "  warning: file-watch hook error: {}: {e}",
+                        hook.command
+                    );
                     errors += 1;
                 }
             }
@@ -593,17 +567,26 @@ pub async fn run_watch_cycle(
     // ── Flake input watch (flake.lock input staleness tracking) ──
     let mut flake_input_updates = 0usize;
     let base_dir = ws.resolved_base_dir()?;
+    // Visibility routes each repo to its base dir; without it everything is under base_dir
+    let watched_repos = if watch_cfg.flake_input_watches.is_empty() {
+        Default::default()
+    } else {
+        sync::resolve_repos(ws, false, api_limiter).await.unwrap_or_else(|e| {
+            display::errln!("  warning: failed to resolve repos for flake input watches: {e}");
+            errors += 1;
+            Default::default()
+        })
+    };
 
     for fiw in &watch_cfg.flake_input_watches {
-        let flake_lock_path = base_dir.join(&fiw.repo).join("flake.lock");
+        let repo_dir = watched_repos.path(ws, &fiw.repo)?;
+        let flake_lock_path = repo_dir.join("flake.lock");
 
         // Parse the locked rev and upstream owner/repo from flake.lock
         let (locked_rev, lock_owner, lock_repo) = match parse_flake_lock_input(&flake_lock_path, &fiw.input) {
             Ok(result) => result,
             Err(e) => {
-                if !quiet {
-                    eprintln!("  warning: failed to parse flake.lock for {}: {e}", fiw.name);
-                }
+                display::errln!("  warning: failed to parse flake.lock for {}: {e}", fiw.name);
                 errors += 1;
                 continue;
             }
@@ -614,9 +597,7 @@ pub async fn run_watch_cycle(
             match upstream.split_once('/') {
                 Some((o, r)) => (o.to_string(), r.to_string()),
                 None => {
-                    if !quiet {
-                        eprintln!("  warning: invalid upstream format for {}: expected owner/repo", fiw.name);
-                    }
+                    display::errln!("  warning: invalid upstream format for {}: expected owner/repo", fiw.name);
                     errors += 1;
                     continue;
                 }
@@ -631,9 +612,7 @@ pub async fn run_watch_cycle(
                 match github.get_repo_head(&upstream_owner, &upstream_repo).await {
                     Ok(sha) => (sha, None),
                     Err(e) => {
-                        if !quiet {
-                            eprintln!("  warning: failed to get HEAD for {}/{}: {e}", upstream_owner, upstream_repo);
-                        }
+                        display::errln!("  warning: failed to get HEAD for {}/{}: {e}", upstream_owner, upstream_repo);
                         errors += 1;
                         continue;
                     }
@@ -643,9 +622,7 @@ pub async fn run_watch_cycle(
                 let tag = match github.get_latest_tag(&upstream_owner, &upstream_repo).await {
                     Ok(t) => t,
                     Err(e) => {
-                        if !quiet {
-                            eprintln!("  warning: failed to get tags for {}/{}: {e}", upstream_owner, upstream_repo);
-                        }
+                        display::errln!("  warning: failed to get tags for {}/{}: {e}", upstream_owner, upstream_repo);
                         errors += 1;
                         continue;
                     }
@@ -660,9 +637,7 @@ pub async fn run_watch_cycle(
                 let sha = match github.get_repo_head(&upstream_owner, &upstream_repo).await {
                     Ok(s) => s,
                     Err(e) => {
-                        if !quiet {
-                            eprintln!("  warning: failed to get HEAD for {}/{}: {e}", upstream_owner, upstream_repo);
-                        }
+                        display::errln!("  warning: failed to get HEAD for {}/{}: {e}", upstream_owner, upstream_repo);
                         errors += 1;
                         continue;
                     }
@@ -694,15 +669,15 @@ pub async fn run_watch_cycle(
         audit.flake_input_stale(&fiw.name, &fiw.repo, &fiw.input, &locked_rev, &upstream_rev);
 
         if !quiet {
-            println!(
+            display::outln!(
                 "  {} flake input stale: {} ({}/{})",
                 "!".yellow().bold(),
                 fiw.name,
                 fiw.repo,
                 fiw.input,
             );
-            println!("    locked:   {}", &locked_rev[..locked_rev.len().min(12)]);
-            println!("    upstream: {}", &upstream_rev[..upstream_rev.len().min(12)]);
+            display::outln!("    locked:   {}", &locked_rev[..locked_rev.len().min(12)]);
+            display::outln!("    upstream: {}", &upstream_rev[..upstream_rev.len().min(12)]);
         }
 
         // Run on_stale post-hooks
@@ -729,7 +704,7 @@ pub async fn run_watch_cycle(
                 .map(|d| shellexpand::tilde(d).to_string());
 
             if !quiet {
-                eprintln!(
+                display::errln!(
                     "  {} running flake-input hook: {} {}",
                     "=>".blue().bold(),
                     hook.command,
@@ -750,21 +725,17 @@ pub async fn run_watch_cycle(
                     let exit_code = status.code().unwrap_or(-1);
                     audit.hook_executed("on_stale", &hook.command, exit_code, duration_ms);
                     if !status.success() && !hook.continue_on_error {
-                        if !quiet {
-                            eprintln!(
-                                "  warning: flake-input hook failed: {} (exit {})",
-                                hook.command, status
-                            );
-                        }
+                        display::errln!(
+                            "  warning: flake-input hook failed: {} (exit {})",
+                            hook.command, status
+                        );
                         errors += 1;
                     }
                 }
                 Err(e) => {
                     let duration_ms = hook_start.elapsed().as_millis() as u64;
                     audit.hook_executed("on_stale", &hook.command, -1, duration_ms);
-                    if !quiet {
-                        eprintln!("  warning: flake-input hook error: {}: {e}", hook.command);
-                    }
+                    display::errln!("  warning: flake-input hook error: {}: {e}", hook.command);
                     errors += 1;
                 }
             }
@@ -772,20 +743,17 @@ pub async fn run_watch_cycle(
 
         // Auto-update: nix flake update <input>
         if fiw.auto_update {
-            let repo_dir = base_dir.join(&fiw.repo);
             if !quiet {
-                eprintln!("  [>>] running nix flake update {} in {}...", fiw.input, fiw.repo);
+                display::errln!("  [>>] running nix flake update {} in {}...", fiw.input, fiw.repo);
             }
             match run_nix_flake_update(&repo_dir, &fiw.input) {
                 Ok(()) => {
                     if !quiet {
-                        eprintln!("  [{}] flake input {} updated", "ok".green(), fiw.input);
+                        display::errln!("  [{}] flake input {} updated", "ok".green(), fiw.input);
                     }
                 }
                 Err(e) => {
-                    if !quiet {
-                        eprintln!("  warning: nix flake update failed for {}: {e}", fiw.input);
-                    }
+                    display::errln!("  warning: nix flake update failed for {}: {e}", fiw.input);
                     errors += 1;
                     // Update cache even on failure to avoid re-triggering
                     state.flake_inputs.insert(fiw.name.clone(), crate::watch_cache::FlakeInputCacheEntry {
@@ -798,7 +766,6 @@ pub async fn run_watch_cycle(
 
             // Auto-commit: git add flake.lock, commit, push
             if fiw.auto_commit {
-                let repo_dir = base_dir.join(&fiw.repo);
                 let flake_lock = repo_dir.join("flake.lock");
                 match auto_commit_flake_input(&repo_dir, &flake_lock, &fiw.input, git_ops) {
                     Ok(()) => {
@@ -806,9 +773,7 @@ pub async fn run_watch_cycle(
                         audit.commit_pushed(&fiw.repo, "(auto)", &msg);
                     }
                     Err(e) => {
-                        if !quiet {
-                            eprintln!("  warning: auto-commit failed for {}: {e}", fiw.repo);
-                        }
+                        display::errln!("  warning: auto-commit failed for {}: {e}", fiw.repo);
                         errors += 1;
                     }
                 }
@@ -817,12 +782,10 @@ pub async fn run_watch_cycle(
             // Auto-propagate: tend flake-update --changed <repo>
             if let Some(ref propagate_repo) = fiw.auto_propagate {
                 if !quiet {
-                    eprintln!("  [>>] propagating flake update for {propagate_repo}...");
+                    display::errln!("  [>>] propagating flake update for {propagate_repo}...");
                 }
                 if let Err(e) = run_flake_propagate(propagate_repo, ws) {
-                    if !quiet {
-                        eprintln!("  warning: auto-propagate failed: {e}");
-                    }
+                    display::errln!("  warning: auto-propagate failed: {e}");
                     errors += 1;
                 }
             }
@@ -840,14 +803,12 @@ pub async fn run_watch_cycle(
 
     if let Some(ref refresh_cfg) = watch_cfg.flake_refresh {
         if refresh_cfg.enable {
-            let all_repos = match sync::resolve_repos(ws, false).await {
+            let all_repos = match sync::resolve_repos(ws, false, api_limiter).await {
                 Ok(r) => r,
                 Err(e) => {
-                    if !quiet {
-                        eprintln!("  warning: failed to resolve repos for flake refresh: {e}");
-                    }
+                    display::errln!("  warning: failed to resolve repos for flake refresh: {e}");
                     errors += 1;
-                    vec![]
+                    Default::default()
                 }
             };
 
@@ -869,9 +830,10 @@ pub async fn run_watch_cycle(
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap_or_default()
                 .as_secs();
+            let env = ws.resolved_env()?;
 
             for repo_name in &eligible {
-                let repo_dir = base_dir.join(repo_name);
+                let repo_dir = all_repos.path(ws, repo_name)?;
 
                 // Must have flake.nix
                 if !repo_dir.join("flake.nix").exists() {
@@ -936,11 +898,22 @@ pub async fn run_watch_cycle(
                     }
                 }
 
+                // A half-done merge or rebase is the user's to finish; same guard as flake-update
+                if let Some(op) = sync::in_progress_operation(&repo_dir, &env) {
+                    if !quiet {
+                        display::print_flake_refresh_skip(
+                            repo_name,
+                            &format!("{} (finish it or run `{}`)", op.label(), op.abort_command()),
+                        );
+                    }
+                    continue;
+                }
+
                 let refresh_start = std::time::Instant::now();
 
                 // Pull before update
                 if refresh_cfg.pull_before_update {
-                    if let Err(e) = git_ops.pull(&repo_dir, &refresh_cfg.branch) {
+                    if let Err(e) = git_ops.pull(&repo_dir, ws.remote_name(), &refresh_cfg.branch) {
                         if !quiet {
                             display::print_flake_refresh_error(
                                 repo_name,
@@ -957,7 +930,7 @@ pub async fn run_watch_cycle(
                 if refresh_cfg.staleness_check {
                     let flake_lock_path = repo_dir.join("flake.lock");
                     if flake_lock_path.exists() {
-                        match check_flake_staleness(&flake_lock_path, &base_dir, &refresh_cfg.branch) {
+                        match check_flake_staleness(&flake_lock_path, &ws.repos_root(&base_dir), &refresh_cfg.branch) {
                             Ok(false) => {
                                 // All inputs are fresh — skip update, record timestamp, bump backoff
                                 let miss_count = state.flake_refresh_misses.entry(repo_name.to_string()).or_insert(0);
@@ -979,14 +952,12 @@ pub async fn run_watch_cycle(
                             Ok(true) => {
                                 // At least one input is stale — proceed with update
                                 if !quiet {
-                                    eprintln!("  [{}] {} has stale inputs, updating...", ">>".yellow(), repo_name);
+                                    display::errln!("  [{}] {} has stale inputs, updating...", ">>".yellow(), repo_name);
                                 }
                             }
                             Err(e) => {
                                 // Can't determine staleness — proceed with update anyway
-                                if !quiet {
-                                    eprintln!("  warning: staleness check failed for {repo_name}: {e}, updating anyway");
-                                }
+                                display::errln!("  warning: staleness check failed for {repo_name}: {e}, updating anyway");
                             }
                         }
                     }
@@ -1093,9 +1064,7 @@ pub async fn run_watch_cycle(
                     // Auto-propagate
                     if refresh_cfg.auto_propagate {
                         if let Err(e) = run_flake_propagate(repo_name, ws) {
-                            if !quiet {
-                                eprintln!("  warning: auto-propagate failed for {repo_name}: {e}");
-                            }
+                            display::errln!("  warning: auto-propagate failed for {repo_name}: {e}");
                             errors += 1;
                         }
                     }
@@ -1132,7 +1101,7 @@ pub async fn run_watch_cycle(
                         .map(|d| shellexpand::tilde(d).to_string());
 
                     if !quiet {
-                        eprintln!(
+                        display::errln!(
                             "  {} running refresh hook: {} {}",
                             "=>".blue().bold(),
                             hook.command,
@@ -1153,21 +1122,17 @@ pub async fn run_watch_cycle(
                             let exit_code = status.code().unwrap_or(-1);
                             audit.hook_executed("after_refresh", &hook.command, exit_code, hook_dur);
                             if !status.success() && !hook.continue_on_error {
-                                if !quiet {
-                                    eprintln!(
-                                        "  warning: refresh hook failed: {} (exit {})",
-                                        hook.command, status
-                                    );
-                                }
+                                display::errln!(
+                                    "  warning: refresh hook failed: {} (exit {})",
+                                    hook.command, status
+                                );
                                 errors += 1;
                             }
                         }
                         Err(e) => {
                             let hook_dur = hook_start.elapsed().as_millis() as u64;
                             audit.hook_executed("after_refresh", &hook.command, -1, hook_dur);
-                            if !quiet {
-                                eprintln!("  warning: refresh hook error: {}: {e}", hook.command);
-                            }
+                            display::errln!("  warning: refresh hook error: {}: {e}", hook.command);
                             errors += 1;
                         }
                     }
@@ -1215,7 +1180,7 @@ async fn run_post_hooks(
             .as_deref()
             .map(|d| shellexpand::tilde(d).to_string());
 
-        eprintln!(
+        display::errln!(
             "  {} running hook: {} {}",
             "=>".blue().bold(),
             hook.command,
@@ -1392,7 +1357,7 @@ fn parse_all_flake_lock_inputs(flake_lock_path: &Path) -> Result<Vec<FlakeLockIn
 
 /// Check if any flake input is stale by comparing locked revs against local git refs.
 ///
-/// For inputs whose repos are cloned locally (in `base_dir`), checks
+/// For inputs whose repos are cloned locally (in the workspace's `repos_root`), checks
 /// `refs/remotes/origin/<branch>` — zero network calls since `git fetch` already ran.
 /// For external repos, falls back to `git ls-remote` (1 call per unique upstream).
 ///
@@ -1498,6 +1463,8 @@ fn auto_commit_flake_input(
 fn run_flake_propagate(changed_repo: &str, ws: &Workspace) -> Result<()> {
     let mut cmd = std::process::Command::new("tend");
     cmd.args(["flake-update", "--changed", changed_repo, "--workspace", &ws.name]);
+    // The caller already holds this workspace's lock
+    cmd.env(crate::lock::HELD_LOCK_ENV, &ws.name);
 
     let output = cmd.output().context("running tend flake-update")?;
 
@@ -1644,9 +1611,9 @@ fn auto_commit_matrix(matrix_file: &Path, git_ops: &dyn GitOps) -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::{CloneMethod, Workspace, WatchConfig};
+    use crate::config::{Workspace, WatchConfig};
     use crate::watch_cache::{RepoState, WatchState};
-    use std::collections::{BTreeMap, HashMap};
+    use std::collections::BTreeMap;
     use std::sync::Mutex;
 
     struct MockGitHub {
@@ -1766,21 +1733,16 @@ mod tests {
         fn commit(&self, _repo_dir: &std::path::Path, _message: &str) -> anyhow::Result<()> { Ok(()) }
         fn push(&self, _repo_dir: &std::path::Path) -> anyhow::Result<()> { Ok(()) }
         fn current_branch(&self, _repo_dir: &std::path::Path) -> anyhow::Result<String> { Ok("main".to_string()) }
-        fn pull(&self, _repo_dir: &std::path::Path, _branch: &str) -> anyhow::Result<()> { Ok(()) }
+        fn pull(&self, _repo_dir: &std::path::Path, _remote: &str, _branch: &str) -> anyhow::Result<()> { Ok(()) }
         fn is_clean(&self, _repo_dir: &std::path::Path) -> anyhow::Result<bool> { Ok(true) }
     }
 
     fn make_test_workspace(name: &str, matrix_file: Option<&str>) -> Workspace {
         Workspace {
             name: name.to_string(),
-            provider: "github".to_string(),
             base_dir: "/tmp/test-tend".to_string(),
-            clone_method: CloneMethod::Ssh,
-            discover: false,
             org: Some("test-org".to_string()),
-            exclude: vec![],
             extra_repos: vec!["repo-a".to_string()],
-            flake_deps: HashMap::new(),
             watch: Some(WatchConfig {
                 enable: true,
                 matrix_file: matrix_file.map(|s| s.to_string()),
@@ -1791,10 +1753,16 @@ mod tests {
                 file_watches: vec![],
                 flake_input_watches: vec![],
                 flake_refresh: None,
+                nix_audit: None,
             }),
+            ..Default::default()
         }
     }
 
+    fn test_limiter() -> tokio::sync::Semaphore {
+        tokio::sync::Semaphore::new(crate::provider::DEFAULT_API_JOBS)
+    }
+
     fn test_audit() -> crate::audit::AuditLog {
         crate::audit::AuditLog::new(std::path::PathBuf::from("/tmp/tend-test-audit.jsonl"))
     }
@@ -1808,7 +1776,7 @@ mod tests {
         let git_ops = MockGitOps;
 
         let audit = test_audit();
-        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit)
+        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit, &test_limiter())
             .await
             .unwrap();
 
@@ -1844,7 +1812,7 @@ repo = "repo-a"
         let git_ops = MockGitOps;
 
         let audit = test_audit();
-        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit)
+        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit, &test_limiter())
             .await
             .unwrap();
 
@@ -1880,7 +1848,7 @@ repo = "repo-a"
         let git_ops = MockGitOps;
 
         let audit = test_audit();
-        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit)
+        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit, &test_limiter())
             .await
             .unwrap();
 
@@ -1917,7 +1885,7 @@ repo = "repo-a"
         let git_ops = MockGitOps;
 
         let audit = test_audit();
-        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit)
+        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit, &test_limiter())
             .await
             .unwrap();
 
@@ -1957,8 +1925,8 @@ repo = "repo-a"
         fn current_branch(&self, _: &std::path::Path) -> anyhow::Result<String> {
             self.calls.lock().unwrap().push("current_branch".into()); Ok("main".to_string())
         }
-        fn pull(&self, _: &std::path::Path, _: &str) -> anyhow::Result<()> {
-            self.calls.lock().unwrap().push("pull".into()); Ok(())
+        fn pull(&self, _: &std::path::Path, remote: &str, branch: &str) -> anyhow::Result<()> {
+            self.calls.lock().unwrap().push(format!("pull {remote} {branch}")); Ok(())
         }
         fn is_clean(&self, _: &std::path::Path) -> anyhow::Result<bool> {
             self.calls.lock().unwrap().push("is_clean".into()); Ok(true)
@@ -1992,7 +1960,7 @@ repo = "repo-a"
         let git_ops = RecordingGitOps::new();
 
         let audit = test_audit();
-        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit)
+        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit, &test_limiter())
             .await
             .unwrap();
 
@@ -2081,7 +2049,7 @@ repo = "repo-a"
         let git_ops = MockGitOps;
 
         let audit = test_audit();
-        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit)
+        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit, &test_limiter())
             .await
             .unwrap();
 
@@ -2130,7 +2098,7 @@ repo = "repo-a"
         let git_ops = MockGitOps;
 
         let audit = test_audit();
-        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit)
+        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit, &test_limiter())
             .await
             .unwrap();
 
@@ -2178,7 +2146,7 @@ repo = "repo-a"
         let git_ops = MockGitOps;
 
         let audit = test_audit();
-        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit)
+        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit, &test_limiter())
             .await
             .unwrap();
 
@@ -2429,7 +2397,7 @@ file_watches:
         let git_ops = MockGitOps;
 
         let audit = test_audit();
-        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit)
+        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit, &test_limiter())
             .await
             .unwrap();
 
@@ -2492,7 +2460,7 @@ file_watches:
         let git_ops = MockGitOps;
 
         let audit = test_audit();
-        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit)
+        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit, &test_limiter())
             .await
             .unwrap();
 
@@ -2544,7 +2512,7 @@ file_watches:
         let git_ops = MockGitOps;
 
         let audit = test_audit();
-        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit)
+        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit, &test_limiter())
             .await
             .unwrap();
 
@@ -2616,7 +2584,7 @@ auto_commit: false
 
         // The download will fail because the URL is unreachable, but the dir should be created
         let audit = test_audit();
-        let result = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit).await;
+        let result = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit, &test_limiter()).await;
 
         // Verify the directory was created before download was attempted
         assert!(download_dir.exists(), "download directory should have been created");
@@ -2678,7 +2646,7 @@ auto_commit: false
         let git_ops = MockGitOps;
 
         let audit = test_audit();
-        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit)
+        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit, &test_limiter())
             .await
             .unwrap();
 
@@ -2916,7 +2884,7 @@ auto_commit: false
         let git_ops = MockGitOps;
 
         let audit = test_audit();
-        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit)
+        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit, &test_limiter())
             .await
             .unwrap();
 
@@ -2967,7 +2935,7 @@ auto_commit: false
         let git_ops = MockGitOps;
 
         let audit = test_audit();
-        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit)
+        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit, &test_limiter())
             .await
             .unwrap();
 
@@ -3012,7 +2980,7 @@ auto_commit: false
         let git_ops = MockGitOps;
 
         let audit = test_audit();
-        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit)
+        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit, &test_limiter())
             .await
             .unwrap();
 
@@ -3065,7 +3033,7 @@ auto_commit: false
         let git_ops = MockGitOps;
 
         let audit = test_audit();
-        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit)
+        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit, &test_limiter())
             .await
             .unwrap();
 
@@ -3120,7 +3088,7 @@ auto_commit: false
         let git_ops = MockGitOps;
 
         let audit = test_audit();
-        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit)
+        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit, &test_limiter())
             .await
             .unwrap();
 
@@ -3179,7 +3147,7 @@ auto_commit: false
         let git_ops = MockGitOps;
 
         let audit = test_audit();
-        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit)
+        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit, &test_limiter())
             .await
             .unwrap();
 
@@ -3237,7 +3205,7 @@ auto_commit: false
         let git_ops = MockGitOps;
 
         let audit = test_audit();
-        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit)
+        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit, &test_limiter())
             .await
             .unwrap();
 
@@ -3287,7 +3255,7 @@ auto_commit: false
         let git_ops = MockGitOps;
 
         let audit = test_audit();
-        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit)
+        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit, &test_limiter())
             .await
             .unwrap();
 
@@ -3330,7 +3298,7 @@ auto_commit: false
         let git_ops = MockGitOps;
 
         let audit = test_audit();
-        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit)
+        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit, &test_limiter())
             .await
             .unwrap();
 
@@ -3372,7 +3340,7 @@ auto_commit: false
         let git_ops = MockGitOps;
 
         let audit = test_audit();
-        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit)
+        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit, &test_limiter())
             .await
             .unwrap();
 
@@ -3428,7 +3396,7 @@ language = "go"
         let git_ops = MockGitOps;
 
         let audit = test_audit();
-        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit)
+        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit, &test_limiter())
             .await
             .unwrap();
 
@@ -3476,7 +3444,7 @@ language = "go"
         let git_ops = RecordingGitOps::new();
 
         let audit = test_audit();
-        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit)
+        let summary = run_watch_cycle(&ws, true, &github, &cache, &appender, &git_ops, &audit, &test_limiter())
             .await
             .unwrap();
 
@@ -3488,4 +3456,39 @@ language = "go"
 
         let _ = std::fs::remove_dir_all(&tmp);
     }
+
+    #[tokio::test]
+    async fn test_flake_refresh_skips_repo_mid_merge_without_pulling() {
+        let tmp = std::env::temp_dir().join(format!("tend-test-refresh-merge-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&tmp);
+        let repo_dir = tmp.join("my-repo");
+        std::fs::create_dir_all(&repo_dir).unwrap();
+        std::process::Command::new("git").args(["init", "-q"]).current_dir(&repo_dir).status().unwrap();
+        std::fs::write(repo_dir.join("flake.nix"), "{}").unwrap();
+        std::fs::write(repo_dir.join(".git/MERGE_HEAD"), "0000000000000000000000000000000000000000\n").unwrap();
+
+        let mut ws = make_test_workspace("refresh-merge", None);
+        ws.base_dir = tmp.to_string_lossy().to_string();
+        ws.extra_repos = vec!["my-repo".to_string()];
+        ws.watch.as_mut().unwrap().flake_refresh = Some(serde_yaml_ng::from_str("enable: true").unwrap());
+
+        let cache = MockCache { state: Mutex::new(WatchState::default()) };
+        let git_ops = RecordingGitOps::new();
+        let summary = run_watch_cycle(&ws, true, &MockGitHub::new(), &cache, &MockAppender::new(), &git_ops, &test_audit(), &test_limiter())
+            .await
+            .unwrap();
+
+        assert_eq!(summary.flake_refreshed, 0);
+        assert!(!git_ops.calls.lock().unwrap().iter().any(|c| c.starts_with("pull")));
+
+        // Once the merge is finished, the pull goes to the workspace's remote
+        std::fs::remove_file(repo_dir.join(".git/MERGE_HEAD")).unwrap();
+        ws.origin_name = Some("upstream".to_string());
+        let _ = run_watch_cycle(&ws, true, &MockGitHub::new(), &cache, &MockAppender::new(), &git_ops, &test_audit(), &test_limiter())
+            .await
+            .unwrap();
+        assert!(git_ops.calls.lock().unwrap().contains(&"pull upstream main".to_string()));
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
 }